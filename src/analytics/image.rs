@@ -2,7 +2,9 @@ use crate::analytics::create_directory;
 use crate::maze::maze_genotype::MazeGenome;
 use crate::mcc::agent::mcc_agent::MCCAgent;
 use crate::neatns::agent::Agent;
-use crate::simulator::{simulate_single_mcc, simulate_single_neatns};
+use crate::simulator::step_budget::get_step_budget;
+use crate::simulator::memo::simulate_memoized;
+use crate::simulator::simulate_single_neatns;
 use crate::visualization::maze::visualize_maze;
 use crate::visualization::simulation::visualize_agent_path;
 use crate::visualization::VisualizationOptions;
@@ -11,18 +13,14 @@ use crate::visualization::VisualizationOptions;
 pub fn visualise_mazes(mazes: &Vec<MazeGenome>, path: &String) {
     for (i, maze) in mazes.iter().enumerate() {
         let maze_seed_path = format!("{}/maze_{}.png", path, i);
-        let maze_phenotype = maze.to_phenotype();
-
-        visualize_maze(&maze_phenotype, maze_seed_path, false);
+        visualize_maze(maze.get_phenotype(), maze_seed_path, false);
     }
 }
 
 #[allow(dead_code)]
 pub fn visualise_maze(maze: &MazeGenome, path: &String) {
     let maze_seed_path = format!("{}", path);
-    let maze_phenotype = maze.to_phenotype();
-
-    visualize_maze(&maze_phenotype, maze_seed_path, false);
+    visualize_maze(maze.get_phenotype(), maze_seed_path, false);
 }
 
 #[allow(dead_code)]
@@ -33,7 +31,7 @@ pub fn visualise_seeds_agent_path(
 ) {
     for (i, maze) in mazes.iter().enumerate() {
         let file_name = format!("maze_{}_solution.png", i);
-        let maze_phenotype = maze.to_phenotype();
+        let maze_phenotype = maze.get_phenotype();
 
         let mut agent_index: Option<u32> = None;
 
@@ -48,13 +46,13 @@ pub fn visualise_seeds_agent_path(
             let agent = &agents[agent_index.unwrap() as usize];
             let simulator_result = simulate_single_neatns(
                 &agent,
-                &maze_phenotype,
-                maze.get_solution_path_cell_length(),
+                maze_phenotype,
+                get_step_budget(maze, maze_phenotype),
                 true,
             );
 
             visualize_agent_path(
-                &maze_phenotype,
+                maze_phenotype,
                 &simulator_result,
                 VisualizationOptions {
                     file_name,
@@ -78,20 +76,13 @@ pub fn visualise_mazes_with_agent_path(
                 .iter()
                 .find(|agent| agent.id == maze.successful_agent_id.unwrap());
 
-            if agent.is_some() {
+            if let Some(agent) = agent {
                 let file_name = format!("maze_solution_{}.png", i);
 
-                let maze_phenotype = maze.to_phenotype();
-
-                let simulator_result = simulate_single_mcc(
-                    &agent.unwrap(),
-                    &maze_phenotype,
-                    maze.get_solution_path_cell_length(),
-                    true,
-                );
+                let simulator_result = simulate_memoized(agent, maze, true);
 
                 visualize_agent_path(
-                    &maze_phenotype,
+                    maze.get_phenotype(),
                     &simulator_result,
                     VisualizationOptions {
                         file_name,
@@ -2,14 +2,22 @@ use std::fs;
 use std::fs::OpenOptions;
 use std::io::prelude::*;
 use std::path::Path;
+use std::time::Duration;
 
+use crate::config;
 use crate::mcc::agent::agent_queue::AgentQueue;
 use crate::mcc::agent::speciated_agent_queue::SpeciatedAgentQueue;
 use crate::mcc::maze::maze_queue::MazeQueue;
 use crate::mcc::maze::speciated_maze_queue::SpeciatedMazeQueue;
+use crate::mcc::species::Assignment;
 use crate::neatns::novelty_archive::euclidean_distance;
-use crate::simulator::{simulate_single_mcc, SimulatorResult};
+use crate::neatns::search::{take_seed_searches, SeedSearch};
+use crate::simulator::memo::simulate_memoized;
+use crate::simulator::step_budget::get_oracle_steps;
+use crate::simulator::trace::TraceFormat;
+use crate::simulator::{evaluate_robustness, SimulatorResult};
 use crate::analytics::image::visualise_mazes_with_agent_path;
+use crate::analytics::text::export_agent_traces;
 use crate::maze::maze_genotype::MazeGenome;
 use crate::mcc::agent::mcc_agent::MCCAgent;
 
@@ -36,6 +44,8 @@ pub struct GenerationStatistics {
     overall_average_agent_size_increase: f64,
     overall_average_maze_size_increase: f64,
     overall_average_maze_complexity_increase: f64,
+    average_functional_agent_size: f64, // Links that can affect an output
+    average_agent_sensor_amount: f64,   // Rangefinder, radar and agent sensor inputs
 }
 
 impl GenerationStatistics {
@@ -58,6 +68,8 @@ impl GenerationStatistics {
         overall_average_agent_size_increase: f64,
         overall_average_maze_size_increase: f64,
         overall_average_maze_complexity_increase: f64,
+        average_functional_agent_size: f64,
+        average_agent_sensor_amount: f64,
     ) -> GenerationStatistics {
         GenerationStatistics {
             generation,
@@ -78,12 +90,14 @@ impl GenerationStatistics {
             overall_average_agent_size_increase,
             overall_average_maze_size_increase,
             overall_average_maze_complexity_increase,
+            average_functional_agent_size,
+            average_agent_sensor_amount,
         }
     }
 
     pub fn to_whitespace_separated_string(&self) -> String {
         let s = format!(
-            "{} {} {} {:.2} {} {} {:.2} {} {} {:.2} {} {} {:.5} {:.5} {:.5} {:.5} {:.5} {:.5}",
+            "{} {} {} {:.2} {} {} {:.2} {} {} {:.2} {} {} {:.5} {:.5} {:.5} {:.5} {:.5} {:.5} {:.2} {:.2}",
             self.generation,
             self.agent_amount,
             self.maze_amount,
@@ -102,16 +116,91 @@ impl GenerationStatistics {
             self.overall_average_agent_size_increase,
             self.overall_average_maze_size_increase,
             self.overall_average_maze_complexity_increase,
+            self.average_functional_agent_size,
+            self.average_agent_sensor_amount,
         );
         s
     }
 }
 
+// Agent children made by one reproduction mode, and the link count change of the viable ones
+#[derive(Debug, Clone, Default)]
+struct ReproductionStatistics {
+    children: u64,
+    viable_children: u64,
+    viable_size_change: i64,
+}
+
+impl ReproductionStatistics {
+    fn add(&mut self, agent: &MCCAgent) {
+        self.children += 1;
+
+        if agent.viable {
+            self.viable_children += 1;
+            self.viable_size_change += agent.genome.links.len() as i64 - agent.parent_size as i64;
+        }
+    }
+
+    fn average_size_change(&self) -> f64 {
+        if self.viable_children == 0 {
+            return 0.0;
+        }
+
+        self.viable_size_change as f64 / self.viable_children as f64
+    }
+
+    fn to_whitespace_separated_string(&self) -> String {
+        format!(
+            "{} {} {:.5}",
+            self.children,
+            self.viable_children,
+            self.average_size_change()
+        )
+    }
+}
+
+// Agent children that went through weight refinement
+#[derive(Debug, Clone, Default)]
+struct RefinementStatistics {
+    children: u64,
+    improved_children: u64,
+    viable_children: u64,
+    evaluations: u64,
+}
+
+impl RefinementStatistics {
+    fn add(&mut self, agent: &MCCAgent) {
+        self.children += 1;
+        self.evaluations += agent.refinement_evaluations as u64;
+
+        if agent.refined {
+            self.improved_children += 1;
+        }
+
+        if agent.viable {
+            self.viable_children += 1;
+        }
+    }
+
+    fn to_whitespace_separated_string(&self) -> String {
+        format!(
+            "{} {} {} {}",
+            self.children, self.improved_children, self.viable_children, self.evaluations
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Analyzer {
     results_path: String,
     generation_stats: Vec<GenerationStatistics>,
     batch_number: u32,
+    evaluations: u64,
+    simulation_time: Duration,
+    crossover_stats: ReproductionStatistics,
+    mutation_stats: ReproductionStatistics,
+    refinement_stats: RefinementStatistics,
+    assignments: Vec<(usize, &'static str, Assignment)>, // Generation and population of each
 }
 
 impl Analyzer {
@@ -132,6 +221,12 @@ impl Analyzer {
             results_path,
             generation_stats: vec![],
             batch_number,
+            evaluations: 0,
+            simulation_time: Duration::from_secs(0),
+            crossover_stats: ReproductionStatistics::default(),
+            mutation_stats: ReproductionStatistics::default(),
+            refinement_stats: RefinementStatistics::default(),
+            assignments: vec![],
         }
     }
 
@@ -171,10 +266,224 @@ impl Analyzer {
         visualise_mazes_with_agent_path(&mazes, &agents, path_string);
     }
 
+    pub fn export_traces(
+        &self,
+        mazes_queue: &SpeciatedMazeQueue,
+        agents_queue: &SpeciatedAgentQueue,
+    ) {
+        let mazes: Vec<MazeGenome> = mazes_queue.iter_individuals().cloned().collect();
+        let agents: Vec<MCCAgent> = agents_queue.iter_individuals().cloned().collect();
+
+        self.write_traces(&mazes, &agents);
+    }
+
+    pub fn export_traces_no_species(&self, mazes_queue: &MazeQueue, agents_queue: &AgentQueue) {
+        let mazes: Vec<MazeGenome> = mazes_queue.iter().cloned().collect();
+        let agents: Vec<MCCAgent> = agents_queue.iter().cloned().collect();
+
+        self.write_traces(&mazes, &agents);
+    }
+
+    fn write_traces(&self, mazes: &[MazeGenome], agents: &[MCCAgent]) {
+        if config::SIMULATOR.trace_format == TraceFormat::None {
+            return;
+        }
+
+        let path_string = format!("{}/traces_{}", self.results_path, self.batch_number);
+
+        if create_directory(path_string.clone()).is_err() {
+            panic!("Could not create trace directory!");
+        }
+
+        export_agent_traces(mazes, agents, path_string, config::SIMULATOR.trace_format);
+    }
+
     pub fn add_generation_stats(&mut self, generation_statistics: &GenerationStatistics) {
         self.generation_stats.push(generation_statistics.clone())
     }
 
+    pub fn add_simulation_time(&mut self, evaluations: usize, duration: Duration) {
+        self.evaluations += evaluations as u64;
+        self.simulation_time += duration;
+    }
+
+    // Called with the evaluated agent children of every generation
+    pub fn add_reproduction_stats(&mut self, agent_children: &[MCCAgent]) {
+        for agent in agent_children.iter() {
+            if agent.crossover {
+                self.crossover_stats.add(agent);
+            } else {
+                self.mutation_stats.add(agent);
+            }
+
+            if agent.refinement_evaluations > 0 {
+                self.refinement_stats.add(agent);
+            }
+        }
+    }
+
+    // Called every generation of speciated runs, takes the assignments made by both queues
+    pub fn add_assignment_stats(
+        &mut self,
+        generation: usize,
+        agents: &mut SpeciatedAgentQueue,
+        mazes: &mut SpeciatedMazeQueue,
+    ) {
+        for assignment in agents.take_assignments() {
+            self.assignments.push((generation, "agent", assignment));
+        }
+
+        for assignment in mazes.take_assignments() {
+            self.assignments.push((generation, "maze", assignment));
+        }
+    }
+
+    // Refinement settings, children refined, children with weights written back,
+    // refined children that became viable and the simulations spent on refinement
+    pub fn generate_refinement_report(&self) {
+        let path_string = format!("{}/refinement_{}.txt", self.results_path, self.batch_number);
+        let path = Path::new(&path_string);
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+
+        let text = format!(
+            "{} {} {}",
+            config::AGENT.refinement_evaluations,
+            config::AGENT.refinement_min_closeness,
+            self.refinement_stats.to_whitespace_separated_string()
+        );
+        if let Err(e) = writeln!(file, "{}", text) {
+            eprintln!("Couldn't write to file: {}", e);
+        }
+    }
+
+    // One line per seed search since the last report: search mode, behaviour, maze width and
+    // height, generations used and whether the maze was solved
+    pub fn generate_seed_search_report(&self) {
+        let path_string = format!("{}/seed_search_{}.txt", self.results_path, self.batch_number);
+        let path = Path::new(&path_string);
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+
+        let searches = take_seed_searches();
+
+        for search in searches.iter() {
+            if let Err(e) = writeln!(file, "{}", search.to_whitespace_separated_string()) {
+                eprintln!("Couldn't write to file: {}", e);
+            }
+        }
+
+        self.generate_speciation_report(&searches);
+    }
+
+    // One line per generation of every seed search: search number, generation,
+    // species count and speciation threshold after the generation
+    fn generate_speciation_report(&self, searches: &[SeedSearch]) {
+        let path_string = format!("{}/speciation_{}.txt", self.results_path, self.batch_number);
+        let path = Path::new(&path_string);
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+
+        for (i, search) in searches.iter().enumerate() {
+            for (generation, (species, threshold)) in search.speciation_history.iter().enumerate() {
+                if let Err(e) = writeln!(file, "{} {} {} {:.4}", i, generation, species, threshold) {
+                    eprintln!("Couldn't write to file: {}", e);
+                }
+            }
+        }
+    }
+
+    // Children, viable children and average size change of viable children,
+    // for crossover children followed by mutation only children
+    pub fn generate_reproduction_report(&self) {
+        let path_string = format!("{}/reproduction_{}.txt", self.results_path, self.batch_number);
+        let path = Path::new(&path_string);
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+
+        let text = format!(
+            "{} {} {}",
+            config::AGENT.crossover_rate,
+            self.crossover_stats.to_whitespace_separated_string(),
+            self.mutation_stats.to_whitespace_separated_string()
+        );
+        if let Err(e) = writeln!(file, "{}", text) {
+            eprintln!("Couldn't write to file: {}", e);
+        }
+    }
+
+    // One line per newcomer put into a species: generation, agent or maze, assignment policy,
+    // species id, distance to that species, distance to the nearest species,
+    // whether the newcomer founded the species and the species count afterwards
+    pub fn generate_assignment_report(&self) {
+        let path_string = format!("{}/assignment_{}.txt", self.results_path, self.batch_number);
+        let path = Path::new(&path_string);
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+
+        for (generation, population, assignment) in self.assignments.iter() {
+            let text = format!(
+                "{} {} {:?} {}",
+                generation,
+                population,
+                config::MCC.species_assignment,
+                assignment.to_whitespace_separated_string()
+            );
+            if let Err(e) = writeln!(file, "{}", text) {
+                eprintln!("Couldn't write to file: {}", e);
+            }
+        }
+    }
+
+    // Agent/maze evaluations per second spent in simulate_many
+    pub fn generate_throughput_report(&self) {
+        let seconds = self.simulation_time.as_secs_f64();
+        let throughput = if seconds > 0.0 {
+            self.evaluations as f64 / seconds
+        } else {
+            0.0
+        };
+
+        println!(
+            "Simulated {} evaluations in {:.2}s ({:.2} evaluations per second)",
+            self.evaluations, seconds, throughput
+        );
+
+        let path_string = format!("{}/throughput_{}.txt", self.results_path, self.batch_number);
+        let path = Path::new(&path_string);
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+
+        let text = format!("{} {:.3} {:.2}", self.evaluations, seconds, throughput);
+        if let Err(e) = writeln!(file, "{}", text) {
+            eprintln!("Couldn't write to file: {}", e);
+        }
+    }
+
     pub fn generate_diversity_score_no_species(&self, agents: &AgentQueue, mazes: &MazeQueue) {
         let path_string = format!("{}/diversity_{}.txt", self.results_path, self.batch_number);
         let path = Path::new(&path_string);
@@ -215,6 +524,82 @@ impl Analyzer {
         }
     }
 
+    pub fn generate_robustness_score(
+        &self,
+        agents: &SpeciatedAgentQueue,
+        mazes: &SpeciatedMazeQueue,
+    ) {
+        let agents: Vec<MCCAgent> = agents.iter_individuals().cloned().collect();
+        let mazes: Vec<MazeGenome> = mazes.iter_individuals().cloned().collect();
+
+        self.write_robustness_score(&agents, &mazes);
+    }
+
+    pub fn generate_robustness_score_no_species(&self, agents: &AgentQueue, mazes: &MazeQueue) {
+        let agents: Vec<MCCAgent> = agents.iter().cloned().collect();
+        let mazes: Vec<MazeGenome> = mazes.iter().cloned().collect();
+
+        self.write_robustness_score(&agents, &mazes);
+    }
+
+    fn write_robustness_score(&self, agents: &[MCCAgent], mazes: &[MazeGenome]) {
+        if config::SIMULATOR.robustness_seeds == 0 {
+            return;
+        }
+
+        let path_string = format!("{}/robustness_{}.txt", self.results_path, self.batch_number);
+        let path = Path::new(&path_string);
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+
+        let text = format!(
+            "{}",
+            calculate_robustness_score(agents, mazes, config::SIMULATOR.robustness_seeds)
+        );
+        if let Err(e) = writeln!(file, "{}", text) {
+            eprintln!("Couldn't write to file: {}", e);
+        }
+    }
+
+    pub fn generate_efficiency_score(
+        &self,
+        agents: &SpeciatedAgentQueue,
+        mazes: &SpeciatedMazeQueue,
+    ) {
+        let agents: Vec<MCCAgent> = agents.iter_individuals().cloned().collect();
+        let mazes: Vec<MazeGenome> = mazes.iter_individuals().cloned().collect();
+
+        self.write_efficiency_score(&agents, &mazes);
+    }
+
+    pub fn generate_efficiency_score_no_species(&self, agents: &AgentQueue, mazes: &MazeQueue) {
+        let agents: Vec<MCCAgent> = agents.iter().cloned().collect();
+        let mazes: Vec<MazeGenome> = mazes.iter().cloned().collect();
+
+        self.write_efficiency_score(&agents, &mazes);
+    }
+
+    fn write_efficiency_score(&self, agents: &[MCCAgent], mazes: &[MazeGenome]) {
+        let path_string = format!("{}/efficiency_{}.txt", self.results_path, self.batch_number);
+        let path = Path::new(&path_string);
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .unwrap();
+
+        let (average_steps_used, average_efficiency) = calculate_efficiency_score(agents, mazes);
+        let text = format!("{:.2} {:.5}", average_steps_used, average_efficiency);
+        if let Err(e) = writeln!(file, "{}", text) {
+            eprintln!("Couldn't write to file: {}", e);
+        }
+    }
+
     pub fn generate_results_files(&self) {
         let path_string = format!("{}/result_{}.txt", self.results_path, self.batch_number);
         let path = Path::new(&path_string);
@@ -241,6 +626,56 @@ fn create_directory(path: String) -> std::io::Result<()> {
     Ok(())
 }
 
+// Average steps used by solving agents, and average ratio of oracle steps to steps used
+fn calculate_efficiency_score(agents: &[MCCAgent], mazes: &[MazeGenome]) -> (f64, f64) {
+    let mut steps_used: Vec<f64> = vec![];
+    let mut efficiencies: Vec<f64> = vec![];
+
+    for maze in mazes.iter() {
+        if let Some(agent_id) = maze.successful_agent_id {
+            if let Some(agent) = agents.iter().find(|agent| agent.id == agent_id) {
+                let simulator_result = simulate_memoized(agent, maze, false);
+
+                if simulator_result.agent_reached_end && simulator_result.steps_used > 0 {
+                    steps_used.push(simulator_result.steps_used as f64);
+                    efficiencies.push(
+                        get_oracle_steps(maze.get_phenotype()) as f64
+                            / simulator_result.steps_used as f64,
+                    );
+                }
+            }
+        }
+    }
+
+    if steps_used.is_empty() {
+        return (0.0, 0.0);
+    }
+
+    (
+        steps_used.iter().sum::<f64>() / steps_used.len() as f64,
+        efficiencies.iter().sum::<f64>() / efficiencies.len() as f64,
+    )
+}
+
+// Average success rate of all solving agent/maze pairs when re-run under noise
+fn calculate_robustness_score(agents: &[MCCAgent], mazes: &[MazeGenome], seeds: u32) -> f64 {
+    let mut success_rates: Vec<f64> = vec![];
+
+    for maze in mazes.iter() {
+        if let Some(agent_id) = maze.successful_agent_id {
+            if let Some(agent) = agents.iter().find(|agent| agent.id == agent_id) {
+                success_rates.push(evaluate_robustness(agent, maze, seeds));
+            }
+        }
+    }
+
+    if success_rates.is_empty() {
+        return 0.0;
+    }
+
+    success_rates.iter().sum::<f64>() / success_rates.len() as f64
+}
+
 fn calculate_diversity_score(agents: &SpeciatedAgentQueue, mazes: &SpeciatedMazeQueue) -> f64 {
     let mut results: Vec<SimulatorResult> = vec![];
     for maze in mazes.iter_individuals() {
@@ -249,15 +684,8 @@ fn calculate_diversity_score(agents: &SpeciatedAgentQueue, mazes: &SpeciatedMaze
                 .iter_individuals()
                 .find(|agent| agent.id == maze.successful_agent_id.unwrap());
 
-            if agent.is_some() {
-                let maze_phenotype = maze.to_phenotype();
-
-                let simulator_result = simulate_single_mcc(
-                    &agent.unwrap(),
-                    &maze_phenotype,
-                    maze.get_solution_path_cell_length(),
-                    true,
-                );
+            if let Some(agent) = agent {
+                let simulator_result = simulate_memoized(agent, maze, true);
 
                 results.push(simulator_result)
             }
@@ -306,15 +734,8 @@ fn calculate_diversity_score_no_species(agents: &AgentQueue, mazes: &MazeQueue)
                 .iter()
                 .find(|agent| agent.id == maze.successful_agent_id.unwrap());
 
-            if agent.is_some() {
-                let maze_phenotype = maze.to_phenotype();
-
-                let simulator_result = simulate_single_mcc(
-                    &agent.unwrap(),
-                    &maze_phenotype,
-                    maze.get_solution_path_cell_length(),
-                    true,
-                );
+            if let Some(agent) = agent {
+                let simulator_result = simulate_memoized(agent, maze, true);
 
                 results.push(simulator_result)
             }
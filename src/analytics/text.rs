@@ -1,9 +1,56 @@
 use std::fs;
 use std::io::Write;
 
+use crate::maze::maze_genotype::MazeGenome;
+use crate::mcc::agent::mcc_agent::MCCAgent;
+use crate::simulator::trace::{trace_to_csv, trace_to_json, TraceFormat};
+use crate::simulator::step_budget::get_step_budget;
+use crate::simulator::trace_single_mcc;
+
 #[allow(dead_code)]
 pub fn write_text_to_file(path: String, text: &[u8]) -> std::io::Result<()> {
     let mut file = fs::File::create(path)?;
     file.write_all(text)?;
     Ok(())
 }
+
+// Writes a per step trace of every solving agent/maze pair, one file per pair.
+// The solving run is replayed with its recorded noise seed, or without noise if none was recorded.
+pub fn export_agent_traces(
+    mazes: &[MazeGenome],
+    agents: &[MCCAgent],
+    folder_path: String,
+    format: TraceFormat,
+) {
+    for maze in mazes.iter() {
+        if let Some(agent_id) = maze.successful_agent_id {
+            if let Some(agent) = agents.iter().find(|agent| agent.id == agent_id) {
+                let maze_phenotype = maze.get_phenotype();
+
+                let simulator_result = trace_single_mcc(
+                    agent,
+                    maze_phenotype,
+                    get_step_budget(maze, maze_phenotype),
+                    maze.successful_noise_seed,
+                );
+
+                let text = match format {
+                    TraceFormat::Json => trace_to_json(&simulator_result.trace),
+                    _ => trace_to_csv(&simulator_result.trace),
+                };
+
+                let path = format!(
+                    "{}/maze_{}_agent_{}.{}",
+                    folder_path,
+                    maze.id,
+                    agent.id,
+                    format.extension()
+                );
+
+                if let Err(e) = write_text_to_file(path, text.as_bytes()) {
+                    eprintln!("Couldn't write trace file: {}", e);
+                }
+            }
+        }
+    }
+}
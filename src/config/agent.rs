@@ -1,3 +1,5 @@
+use crate::neatns::network::activation::Activation;
+use crate::neatns::network::substrate::Encoding;
 use envconfig::Envconfig;
 
 #[derive(Envconfig)]
@@ -11,9 +13,96 @@ pub struct Config {
     #[envconfig(from = "add_neuron", default = "0.01")]
     pub add_neuron: f64,
 
+    // Recurrent links are only added when this is above zero
+    #[envconfig(from = "add_recurrent_connection", default = "0.0")]
+    pub add_recurrent_connection: f64,
+
     #[envconfig(from = "disable_connection", default = "0.005")]
     pub disable_connection: f64,
 
+    // Links only become plastic when this is above zero
+    #[envconfig(from = "mutate_plasticity", default = "0.0")]
+    pub mutate_plasticity: f64,
+
+    // Gain and Gaussian width mutations use the sizes of config::NEAT
+    #[envconfig(from = "mutate_node_parameters", default = "0.0")]
+    pub mutate_node_parameters: f64,
+
+    #[envconfig(from = "enable_connection", default = "0.0")]
+    pub enable_connection: f64,
+
+    // Bias and activation mutations use the mutation sizes and activations of config::NEAT
+    #[envconfig(from = "mutate_hidden_bias", default = "0.0")]
+    pub mutate_hidden_bias: f64,
+
+    #[envconfig(from = "mutate_hidden_activation", default = "0.0")]
+    pub mutate_hidden_activation: f64,
+
+    #[envconfig(from = "mutate_output_bias", default = "0.0")]
+    pub mutate_output_bias: f64,
+
+    #[envconfig(from = "mutate_output_activation", default = "0.0")]
+    pub mutate_output_activation: f64,
+
+    // Remove disabled links and hidden nodes that can not reach an output from mutated children
+    #[envconfig(from = "simplify_children", default = "false")]
+    pub simplify_children: bool,
+
+    // Weight hill-climbing simulations for children that solve no maze, 0 disables refinement
+    #[envconfig(from = "refinement_evaluations", default = "0")]
+    pub refinement_evaluations: u32,
+
+    // Only children at least this close to solving a maze are refined, 1.0 is at the goal
+    #[envconfig(from = "refinement_min_closeness", default = "0.8")]
+    pub refinement_min_closeness: f64,
+
+    #[envconfig(from = "refinement_weight_size", default = "0.1")]
+    pub refinement_weight_size: f64,
+
+    // Chance that a child gets a second parent from the same queue or species
+    #[envconfig(from = "agent_crossover_rate", default = "0.0")]
+    pub crossover_rate: f64,
+
+    // Direct or Cppn, Cppn genomes paint the weights of a fixed substrate network
+    #[envconfig(from = "agent_encoding", default = "Direct")]
+    pub encoding: Encoding,
+
+    #[envconfig(from = "substrate_hidden_nodes", default = "8")]
+    pub substrate_hidden_nodes: usize,
+
+    // Weaker links are not expressed
+    #[envconfig(from = "substrate_weight_threshold", default = "0.2")]
+    pub substrate_weight_threshold: f64,
+
+    #[envconfig(from = "substrate_max_weight", default = "3.0")]
+    pub substrate_max_weight: f64,
+
+    #[envconfig(from = "substrate_hidden_activation", default = "Sigmoid")]
+    pub substrate_hidden_activation: Activation,
+
+    #[envconfig(from = "substrate_output_activation", default = "None")]
+    pub substrate_output_activation: Activation,
+
+    // Sensor layout mutations of MCC agents, input nodes are added and removed to match
+    #[envconfig(from = "add_rangefinder", default = "0.0")]
+    pub add_rangefinder: f64,
+
+    #[envconfig(from = "remove_rangefinder", default = "0.0")]
+    pub remove_rangefinder: f64,
+
+    // Turns one rangefinder by up to rangefinder_angle_size degrees
+    #[envconfig(from = "mutate_rangefinder_angle", default = "0.0")]
+    pub mutate_rangefinder_angle: f64,
+
+    #[envconfig(from = "rangefinder_angle_size", default = "15.0")]
+    pub rangefinder_angle_size: f64,
+
+    #[envconfig(from = "toggle_radar", default = "0.0")]
+    pub toggle_radar: f64,
+
+    #[envconfig(from = "max_rangefinders", default = "12")]
+    pub max_rangefinders: usize,
+
     #[envconfig(from = "start_offset", default = "315.0")] // pointing south east
     pub start_offset: f64,
 
@@ -14,6 +14,10 @@ pub struct Config {
     #[envconfig(from = "run_replacement_experiment", default = "true")]
     pub run_replacement_experiment: bool,
 
+    // Agents share each maze in groups and sense each other, seed genomes get agent sensor inputs
+    #[envconfig(from = "run_multi_agent_experiment", default = "false")]
+    pub run_multi_agent_experiment: bool,
+
     #[envconfig(from = "batches", default = "5")]
     pub batches: u32,
 }
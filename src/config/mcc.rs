@@ -1,3 +1,4 @@
+use crate::mcc::species::{CentroidUpdate, SpeciesAssignment};
 use envconfig::Envconfig;
 
 #[derive(Envconfig)]
@@ -43,4 +44,40 @@ pub struct Config {
 
     #[envconfig(from = "replacement_generations_between_search", default = "100")]
     pub replacement_generations_between_search: usize,
+
+    // Fixed, Medoid or AverageLinkage, what members of a speciated MCC species are compared against.
+    // AverageLinkage does not keep or update a centroid, it uses the mean distance to all members.
+    // Placing a newcomer then costs one genome distance per individual in the population instead
+    // of per species, and species distances and split checks cost distances between all pairs.
+    #[envconfig(from = "species_centroid", default = "Fixed")]
+    pub species_centroid: CentroidUpdate,
+
+    // Generations between centroid updates, splits and merges of species, 0 disables them
+    #[envconfig(from = "species_update_interval", default = "10")]
+    pub species_update_interval: usize,
+
+    // Species whose members are on average further than this from the species are split, 0 disables
+    #[envconfig(from = "agent_species_split_distance", default = "0.0")]
+    pub agent_species_split_distance: f64,
+
+    #[envconfig(from = "maze_species_split_distance", default = "0.0")]
+    pub maze_species_split_distance: f64,
+
+    // Species closer than this to each other are merged, 0 disables
+    #[envconfig(from = "agent_species_merge_distance", default = "0.0")]
+    pub agent_species_merge_distance: f64,
+
+    #[envconfig(from = "maze_species_merge_distance", default = "0.0")]
+    pub maze_species_merge_distance: f64,
+
+    // Farthest, Nearest, Threshold or Probabilistic, how newcomers are put into species
+    #[envconfig(from = "species_assignment", default = "Farthest")]
+    pub species_assignment: SpeciesAssignment,
+
+    // Under Threshold, newcomers further than this from every species found a new species
+    #[envconfig(from = "agent_species_assignment_threshold", default = "0.5")]
+    pub agent_species_assignment_threshold: f64,
+
+    #[envconfig(from = "maze_species_assignment_threshold", default = "10.0")]
+    pub maze_species_assignment_threshold: f64,
 }
@@ -8,6 +8,7 @@ mod maze;
 mod mcc;
 mod neat;
 mod neatns;
+mod simulator;
 
 lazy_static! {
     pub static ref EXPERIMENTS: experiments::Config = experiments::Config::init().unwrap();
@@ -16,4 +17,5 @@ lazy_static! {
     pub static ref AGENT: agent::Config = agent::Config::init().unwrap();
     pub static ref NEAT: neat::Config = neat::Config::init().unwrap();
     pub static ref NEATNS: neatns::Config = neatns::Config::init().unwrap();
+    pub static ref SIMULATOR: simulator::Config = simulator::Config::init().unwrap();
 }
@@ -12,6 +12,16 @@ pub struct Config {
     #[envconfig(from = "SPECIATION_THRESHOLD", default = "0.85")]
     pub speciation_threshold: f64,
 
+    // Species count the threshold is adjusted towards every generation, 0 keeps it fixed
+    #[envconfig(from = "TARGET_SPECIES", default = "0")]
+    pub target_species: usize,
+
+    #[envconfig(from = "SPECIATION_THRESHOLD_STEP", default = "0.05")]
+    pub speciation_threshold_step: f64,
+
+    #[envconfig(from = "SPECIATION_THRESHOLD_MIN", default = "0.05")]
+    pub speciation_threshold_min: f64,
+
     #[envconfig(from = "INTERSPECIES_REPRODUCTION_CHANCE", default = "0.15")]
     pub interspecies_reproduction_chance: f64,
 
@@ -24,6 +34,10 @@ pub struct Config {
     #[envconfig(from = "ADD_CONNECTION_PROBABILITY", default = "0.08")]
     pub add_connection_probability: f64,
 
+    // Recurrent links are only added when this is above zero
+    #[envconfig(from = "ADD_RECURRENT_CONNECTION_PROBABILITY", default = "0.0")]
+    pub add_recurrent_connection_probability: f64,
+
     #[envconfig(from = "DISABLE_CONNECTION_PROBABILITY", default = "0.05")]
     pub disable_connection_probability: f64,
 
@@ -36,6 +50,27 @@ pub struct Config {
     #[envconfig(from = "INITIAL_LINK_WEIGHT_SIZE", default = "0.5")]
     pub initial_link_weight_size: f64,
 
+    // Links only become plastic when this is above zero
+    #[envconfig(from = "MUTATE_PLASTICITY_PROBABILITY", default = "0.0")]
+    pub mutate_plasticity_probability: f64,
+
+    #[envconfig(from = "MUTATE_PLASTICITY_SIZE", default = "0.1")]
+    pub mutate_plasticity_size: f64,
+
+    // Plastic weights are kept within plus/minus this value
+    #[envconfig(from = "PLASTIC_WEIGHT_LIMIT", default = "5.0")]
+    pub plastic_weight_limit: f64,
+
+    // Gain and Gaussian width of a node
+    #[envconfig(from = "MUTATE_NODE_PARAMETERS_PROBABILITY", default = "0.0")]
+    pub mutate_node_parameters_probability: f64,
+
+    #[envconfig(from = "MUTATE_NODE_PARAMETERS_SIZE", default = "0.1")]
+    pub mutate_node_parameters_size: f64,
+
+    #[envconfig(from = "GAUSSIAN_WIDTH_MIN", default = "0.05")]
+    pub gaussian_width_min: f64,
+
     #[envconfig(from = "MUTATE_HIDDEN_BIAS_PROBABILIT", default = "0.8")]
     pub mutate_hidden_bias_probability: f64,
 
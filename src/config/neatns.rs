@@ -1,7 +1,44 @@
+use crate::neatns::behaviour::BehaviourType;
+use crate::neatns::search::SearchMode;
 use envconfig::Envconfig;
 
 #[derive(Envconfig)]
 pub struct Config {
     #[envconfig(from = "AMOUNT_OF_NEIGHBORS", default = "15")]
     pub amount_of_neighbors: usize,
+
+    // Initial novelty needed to enter the archive, adapted during the search
+    #[envconfig(from = "NOVELTY_THRESHOLD", default = "0.5")]
+    pub novelty_threshold: f64,
+
+    #[envconfig(from = "NOVELTY_THRESHOLD_MIN", default = "0.05")]
+    pub novelty_threshold_min: f64,
+
+    // The threshold is raised when more are added in one generation
+    #[envconfig(from = "ARCHIVE_ADD_LIMIT", default = "4")]
+    pub archive_add_limit: usize,
+
+    // The threshold is lowered after this many generations without additions
+    #[envconfig(from = "ARCHIVE_STAGNATION_GENERATIONS", default = "10")]
+    pub archive_stagnation_generations: usize,
+
+    #[envconfig(from = "ARCHIVE_SIZE_LIMIT", default = "0")] // 0 means unbounded
+    pub archive_size_limit: usize,
+
+    #[envconfig(from = "ARCHIVE_CELL_SIZE", default = "1.0")] // Maze cells per spatial index cell
+    pub archive_cell_size: f64,
+
+    // FinalPosition, Trajectory, CellVisits or FinalPositionTime
+    #[envconfig(from = "BEHAVIOUR", default = "FinalPosition")]
+    pub behaviour: BehaviourType,
+
+    // Objective, Novelty, Blend, LocalCompetition or Random
+    #[envconfig(from = "SEARCH_MODE", default = "Novelty")]
+    pub search_mode: SearchMode,
+
+    #[envconfig(from = "NOVELTY_WEIGHT", default = "0.5")] // Share of novelty in Blend fitness
+    pub novelty_weight: f64,
+
+    #[envconfig(from = "TRAJECTORY_INTERVAL", default = "50")] // Steps between trajectory samples
+    pub trajectory_interval: u32,
 }
@@ -0,0 +1,77 @@
+use crate::neatns::network::activation::OutputSquashing;
+use crate::simulator::noise::NoiseType;
+use crate::simulator::step_budget::StepBudgetPolicy;
+use crate::simulator::trace::TraceFormat;
+use envconfig::Envconfig;
+
+#[derive(Envconfig)]
+pub struct Config {
+    #[envconfig(from = "noise_type", default = "None")] // None, Gaussian or Uniform
+    pub noise_type: NoiseType,
+
+    #[envconfig(from = "sensor_noise", default = "0.0")]
+    pub sensor_noise: f64,
+
+    #[envconfig(from = "radar_noise", default = "0.0")]
+    pub radar_noise: f64,
+
+    #[envconfig(from = "motor_noise", default = "0.0")]
+    pub motor_noise: f64,
+
+    #[envconfig(from = "output_squashing", default = "None")] // None, Tanh or Clamp
+    pub output_squashing: OutputSquashing,
+
+    #[envconfig(from = "output_limit", default = "1.0")] // Bound of squashed outputs
+    pub output_limit: f64,
+
+    #[envconfig(from = "robustness_seeds", default = "0")]
+    pub robustness_seeds: u32,
+
+    #[envconfig(from = "trace_format", default = "None")] // None, Csv or Json
+    pub trace_format: TraceFormat,
+
+    // Steps a repeated state is looked for, 0 disables early termination.
+    // Only stateless networks without noise are stopped, as only their runs repeat exactly.
+    #[envconfig(from = "stuck_window", default = "0")]
+    pub stuck_window: usize,
+
+    #[envconfig(from = "exit_on_no_displacement", default = "true")]
+    pub exit_on_no_displacement: bool,
+
+    #[envconfig(from = "exit_on_oscillation", default = "true")]
+    pub exit_on_oscillation: bool,
+
+    #[envconfig(from = "exit_on_spin", default = "true")]
+    pub exit_on_spin: bool,
+
+    // SolutionPath, ShortestPath, MazeArea, Fixed or OracleSlack
+    #[envconfig(from = "step_budget_policy", default = "SolutionPath")]
+    pub step_budget_policy: StepBudgetPolicy,
+
+    #[envconfig(from = "step_budget_multiplier", default = "1.0")]
+    pub step_budget_multiplier: f64,
+
+    #[envconfig(from = "step_budget_fixed_steps", default = "1000")]
+    pub step_budget_fixed_steps: u32,
+
+    #[envconfig(from = "agent_collisions", default = "true")] // Multi agent simulations only
+    pub agent_collisions: bool,
+
+    #[envconfig(from = "agent_sensor_range", default = "2.0")] // Cells
+    pub agent_sensor_range: f64,
+
+    // Agents released into a maze together by the multi-agent experiment
+    #[envconfig(from = "multi_agent_group_size", default = "4")]
+    pub multi_agent_group_size: usize,
+
+    #[envconfig(from = "simulation_threads", default = "0")] // 0 uses all available cores
+    pub simulation_threads: usize,
+
+    // Reuse outcomes of solving agent/maze pairs by id. With noise enabled the first noisy outcome is kept
+    #[envconfig(from = "memoize_evaluations", default = "true")]
+    pub memoize_evaluations: bool,
+
+    // Solving pairs kept, the oldest are forgotten first
+    #[envconfig(from = "memo_capacity", default = "1000")]
+    pub memo_capacity: usize,
+}
@@ -59,6 +59,13 @@ fn main() {
             mcc::experiments::species_replacement::run_replacement_experiment(&mut analyzer);
             analyzer.generate_results_files();
         }
+
+        if config::EXPERIMENTS.run_multi_agent_experiment {
+            let results_path = format!("{}/multi_agent_experiment", results_base_path);
+            let mut analyzer = Analyzer::new(results_path, i);
+            mcc::experiments::multi_agent::run_multi_agent_experiment(&mut analyzer);
+            analyzer.generate_results_files();
+        }
     }
 }
 
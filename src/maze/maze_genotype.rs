@@ -1,6 +1,7 @@
 use core::fmt;
 use std::cmp::max;
 use std::i32;
+use std::sync::{Arc, OnceLock};
 
 use rand::{thread_rng, Rng};
 
@@ -63,6 +64,10 @@ pub struct MazeGenome {
     pub(crate) viable: bool,
     pub(crate) id: u32,
     pub successful_agent_id: Option<u32>,
+    // Noise seed of the solving run, None if it was not recorded, as for NEAT-NS seeds
+    pub successful_noise_seed: Option<u64>,
+    // Phenotype built on first use, cleared whenever the genome is mutated
+    phenotype: OnceLock<Arc<MazePhenotype>>,
 }
 
 impl MazeGenome {
@@ -83,6 +88,8 @@ impl MazeGenome {
             viable: true,
             id,
             successful_agent_id: None,
+            successful_noise_seed: None,
+            phenotype: OnceLock::new(),
         }
     }
 
@@ -213,6 +220,15 @@ impl MazeGenome {
         phenotype
     }
 
+    /// Cached phenotype, built on first call
+    pub fn get_phenotype(&self) -> &MazePhenotype {
+        self.phenotype.get_or_init(|| Arc::new(self.to_phenotype()))
+    }
+
+    fn clear_phenotype(&mut self) {
+        self.phenotype = OnceLock::new();
+    }
+
     pub fn mutate(&mut self) {
         let mut rng = rand::thread_rng();
 
@@ -252,6 +268,8 @@ impl MazeGenome {
     }
 
     pub fn mutate_wall(&mut self) {
+        self.clear_phenotype();
+
         let mut rng = thread_rng();
 
         let index = (rng.gen::<f32>() * self.wall_genes.len() as f32) as usize;
@@ -259,6 +277,8 @@ impl MazeGenome {
     }
 
     pub fn mutate_passage(&mut self) {
+        self.clear_phenotype();
+
         let mut rng = thread_rng();
 
         let index = (rng.gen::<f32>() * self.wall_genes.len() as f32) as usize;
@@ -266,6 +286,8 @@ impl MazeGenome {
     }
 
     pub fn mutate_waypoint(&mut self) {
+        self.clear_phenotype();
+
         let mut rng = thread_rng();
 
         let index = (rng.gen::<f32>() * self.path_genes.len() as f32) as usize;
@@ -391,6 +413,8 @@ impl MazeGenome {
     }
 
     pub fn add_wall(&mut self) {
+        self.clear_phenotype();
+
         let mut rng = rand::thread_rng();
 
         self.wall_genes.push(WallGene::new(
@@ -402,6 +426,8 @@ impl MazeGenome {
     }
 
     pub fn delete_wall(&mut self) {
+        self.clear_phenotype();
+
         if self.wall_genes.len() <= 1 {
             return;
         }
@@ -446,6 +472,7 @@ impl MazeGenome {
                 &clone.path_genes,
             ) {
                 self.path_genes.push(path_gene);
+                self.clear_phenotype();
                 return true;
             } else {
                 return false;
@@ -455,6 +482,8 @@ impl MazeGenome {
     }
 
     pub fn increase_size(&mut self) {
+        self.clear_phenotype();
+
         self.height += 1;
         self.width += 1;
     }
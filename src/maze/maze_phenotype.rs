@@ -1,3 +1,6 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
 use math::round;
 
 use crate::maze::maze_genotype::{PathGene, WallGene};
@@ -92,6 +95,58 @@ impl MazePhenotype {
         &self.grid[x as usize][y as usize]
     }
 
+    // Breadth first search from the start cell to the goal cell.
+    // Returns the visited cells in order, including start and goal.
+    pub fn get_shortest_path(&self) -> Option<Vec<(u32, u32)>> {
+        let start = (0, self.height - 1);
+        let goal = (self.width - 1, 0);
+
+        let mut previous: HashMap<(u32, u32), (u32, u32)> = HashMap::new();
+        let mut queue: VecDeque<(u32, u32)> = VecDeque::new();
+        queue.push_back(start);
+        previous.insert(start, start);
+
+        while let Some((x, y)) = queue.pop_front() {
+            if (x, y) == goal {
+                let mut path = vec![goal];
+                let mut current = goal;
+
+                while current != start {
+                    current = previous[&current];
+                    path.push(current);
+                }
+                path.reverse();
+
+                return Some(path);
+            }
+
+            let cell = self.get_cell_at(x, y);
+            let mut neighbors: Vec<(u32, u32)> = vec![];
+
+            if !cell.north_wall && y + 1 < self.height {
+                neighbors.push((x, y + 1));
+            }
+            if !cell.east_wall && x + 1 < self.width {
+                neighbors.push((x + 1, y));
+            }
+            if !cell.south_wall && y > 0 {
+                neighbors.push((x, y - 1));
+            }
+            if !cell.west_wall && x > 0 {
+                neighbors.push((x - 1, y));
+            }
+
+            for neighbor in neighbors {
+                if let Entry::Vacant(entry) = previous.entry(neighbor) {
+                    entry.insert((x, y));
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        None
+    }
+
     pub fn update_cell_is_juncture(&mut self, x: u32, y: u32, is_juncture: bool) {
         self.grid[x as usize][y as usize].is_juncture = is_juncture;
     }
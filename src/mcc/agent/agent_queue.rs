@@ -1,21 +1,22 @@
+use rand::Rng;
+
+use crate::config;
 use crate::mcc::agent::mcc_agent::MCCAgent;
+use crate::mcc::next_individual_id;
 
 #[derive(Clone)]
 pub struct AgentQueue {
     agents: Vec<MCCAgent>,
     current_agent_index: usize,
     pub max_items_limit: u32,
-    total_individuals_added: u32,
 }
 
 impl AgentQueue {
     pub fn new(mcc_agents: Vec<MCCAgent>, max_items_limit: u32) -> AgentQueue {
-        let total_individuals_added = mcc_agents.len() as u32;
         AgentQueue {
             agents: mcc_agents,
             current_agent_index: 0,
             max_items_limit,
-            total_individuals_added,
         }
     }
 
@@ -48,20 +49,35 @@ impl AgentQueue {
 
     pub fn get_children(&mut self, amount: usize) -> Vec<MCCAgent> {
         let mut children: Vec<MCCAgent> = vec![];
+        let mut rng = rand::thread_rng();
 
         for _ in 0..amount {
             if self.current_agent_index >= self.agents.len() {
                 self.current_agent_index = 0;
             }
 
-            children.push(self.agents.get(self.current_agent_index).unwrap().clone());
+            let parent = self.agents.get(self.current_agent_index).unwrap();
+
+            // The second parent is any other agent in the queue
+            if self.agents.len() > 1 && rng.gen::<f64>() < config::AGENT.crossover_rate {
+                let mut other_index = rng.gen_range(0, self.agents.len() - 1);
+                if other_index >= self.current_agent_index {
+                    other_index += 1;
+                }
+
+                children.push(parent.crossover(&self.agents[other_index]));
+            } else {
+                let mut child = parent.clone();
+                child.crossover = false;
+                child.parent_size = parent.genome.links.len();
+                children.push(child);
+            }
             self.current_agent_index =
                 (self.current_agent_index + 1) % self.max_items_limit as usize;
         }
 
         for child in children.iter_mut() {
-            child.id = self.total_individuals_added;
-            self.total_individuals_added += 1;
+            child.id = next_individual_id();
 
             child.mutate();
             child.viable = false;
@@ -88,4 +104,14 @@ impl AgentQueue {
 
         sum as f64 / self.agents.len() as f64
     }
+
+    /// Average of a count taken from every agent, such as its functional size
+    pub fn get_average_of<F>(&self, metric: F) -> f64
+    where
+        F: Fn(&MCCAgent) -> usize,
+    {
+        let sum: usize = self.agents.iter().map(metric).sum();
+
+        sum as f64 / self.agents.len() as f64
+    }
 }
@@ -1,5 +1,7 @@
+use crate::config;
 use crate::mcc::agent::agent_queue::AgentQueue;
 use crate::mcc::agent::mcc_agent::MCCAgent;
+use crate::mcc::species::{medoid, partition, split_capacity, CentroidUpdate};
 
 #[derive(Clone)]
 pub struct AgentSpeciesStatistics {
@@ -11,6 +13,10 @@ pub struct AgentSpeciesStatistics {
 
 impl AgentSpeciesStatistics {
     pub fn get_overall_average_increase(&self) -> f64 {
+        if self.average_size_increases.is_empty() {
+            return 0.0;
+        }
+
         self.average_size_increases.iter().sum::<f64>() as f64
             / self.average_size_increases.len() as f64
     }
@@ -65,7 +71,81 @@ impl AgentSpecies {
     }
 
     pub fn distance(&self, other: &MCCAgent) -> f64 {
-        self.centroid.distance(other)
+        match config::MCC.species_centroid {
+            CentroidUpdate::AverageLinkage => {
+                self.iter().map(|a| a.distance(other)).sum::<f64>() / self.len() as f64
+            }
+            _ => self.centroid.distance(other),
+        }
+    }
+
+    /// Distance between two species, from the members of 'other' to this species
+    pub fn species_distance(&self, other: &AgentSpecies) -> f64 {
+        match config::MCC.species_centroid {
+            CentroidUpdate::AverageLinkage => {
+                other.iter().map(|a| self.distance(a)).sum::<f64>() / other.len() as f64
+            }
+            _ => self.distance(&other.centroid),
+        }
+    }
+
+    /// Average distance of the members to the species
+    pub fn spread(&self) -> f64 {
+        self.iter().map(|a| self.distance(a)).sum::<f64>() / self.len() as f64
+    }
+
+    pub fn update_centroid(&mut self) {
+        if config::MCC.species_centroid == CentroidUpdate::Medoid {
+            let agents: Vec<&MCCAgent> = self.iter().collect();
+            let index = medoid(agents.len(), |i, j| agents[i].distance(agents[j]));
+
+            self.centroid = agents[index].clone();
+        }
+    }
+
+    /// Splits off the members closer to a distant member than to the other side of the species.
+    /// Capacity is divided by the size of both parts, and the new species inherits the statistics.
+    /// A species with a capacity below two is not split, as one part would get no room.
+    pub fn split(&mut self, id: u32) -> Option<AgentSpecies> {
+        let agents: Vec<&MCCAgent> = self.iter().collect();
+        let ((centroid, members), (new_centroid, new_members)) = partition(
+            agents.len(),
+            2,
+            |i| self.distance(agents[i]),
+            |i, j| agents[i].distance(agents[j]),
+        )?;
+
+        let (capacity, new_capacity) = split_capacity(
+            self.agent_queue.max_items_limit,
+            members.len(),
+            new_members.len(),
+        )?;
+        let collect = |indices: &[usize]| indices.iter().map(|i| agents[*i].clone()).collect();
+
+        let mut new_species = AgentSpecies {
+            centroid: agents[new_centroid].clone(),
+            agent_queue: AgentQueue::new(collect(&new_members), new_capacity),
+            id,
+            statistics: self.statistics.clone(),
+        };
+        let (centroid, members) = (agents[centroid].clone(), collect(&members));
+
+        self.centroid = centroid;
+        self.agent_queue = AgentQueue::new(members, capacity);
+
+        self.update_centroid();
+        new_species.update_centroid();
+
+        Some(new_species)
+    }
+
+    /// Takes in all members and the capacity of another species
+    pub fn merge(&mut self, other: AgentSpecies) {
+        let agents = self.iter().chain(other.iter()).cloned().collect();
+        let capacity = self.agent_queue.max_items_limit + other.agent_queue.max_items_limit;
+
+        self.agent_queue = AgentQueue::new(agents, capacity);
+        self.update_centroid();
     }
 
     pub fn save_state(&mut self) {
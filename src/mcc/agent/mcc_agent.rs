@@ -1,34 +1,113 @@
 use core::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
 
-use crate::mcc::agent::agent_genome::AgentGenome;
-use crate::mcc::agent::neural_network::NeuralNetwork;
+use lazy_static::lazy_static;
+
+use crate::config;
+use crate::mcc::next_individual_id;
 use crate::neatns::agent::Agent;
+use crate::neatns::network::genome::Genome;
+use crate::neatns::network::innovation::{InnovationLog, InnovationTime};
+use crate::neatns::network::mutation::MutationProfile;
+use crate::neatns::network::neural_network::NeuralNetwork;
+use crate::neatns::network::substrate;
+use crate::simulator::morphology::Morphology;
+
+lazy_static! {
+    // Innovation history shared by all MCC agents, so equal structural mutations get equal numbers
+    static ref INNOVATIONS: Mutex<(InnovationLog, InnovationTime)> =
+        Mutex::new((InnovationLog::new(), InnovationTime::new()));
+}
 
 #[derive(Clone)]
 pub struct MCCAgent {
-    pub genome: AgentGenome,
+    pub genome: Genome,
+    pub morphology: Morphology, // Sensors, evolved alongside the genome
     pub viable: bool,
     pub id: u32,
     pub completed_maze_id: Option<u32>,
+    pub(crate) crossover: bool, // Produced by crossover rather than mutation only
+    pub(crate) parent_size: usize,
+    pub(crate) refinement_evaluations: u32, // Simulations spent on weight refinement
+    pub(crate) refined: bool,               // Refined weights were written back into the genome
+    // Network built on first use, cleared whenever the genome is mutated
+    network: OnceLock<Arc<NeuralNetwork>>,
 }
 
 impl MCCAgent {
     pub fn new(agent: Agent) -> MCCAgent {
+        // Seeds come from separate populations, new numbers must not collide with any of them
+        {
+            let mut innovations = INNOVATIONS.lock().unwrap();
+            let next = agent.genome.next_innovation_time();
+
+            innovations.1.node_number = innovations.1.node_number.max(next.node_number);
+            innovations.1.innovation_number =
+                innovations.1.innovation_number.max(next.innovation_number);
+        }
+
         MCCAgent {
-            genome: AgentGenome::new(agent.genome),
+            genome: agent.genome,
+            morphology: agent.morphology,
             viable: true,
             id: agent.id,
             completed_maze_id: Option::None,
+            crossover: false,
+            parent_size: 0,
+            refinement_evaluations: 0,
+            refined: false,
+            network: OnceLock::new(),
+        }
+    }
+
+    /// Child with the structure of this agent, links present in both parents are crossed over
+    pub fn crossover(&self, other: &Self) -> MCCAgent {
+        MCCAgent {
+            genome: self.genome.crossover(&other.genome, true),
+            morphology: self.morphology.clone(),
+            viable: false,
+            id: self.id,
+            completed_maze_id: Option::None,
+            crossover: true,
+            parent_size: self.genome.links.len(),
+            refinement_evaluations: 0,
+            refined: false,
+            network: OnceLock::new(),
         }
     }
 
+    /// Returns a copy of the cached network, as activation writes to its internal buffers
     pub fn to_phenotype(&self) -> NeuralNetwork {
-        NeuralNetwork::new(&self.genome)
+        self.network
+            .get_or_init(|| Arc::new(substrate::build_network(&self.genome, &self.morphology)))
+            .as_ref()
+            .clone()
     }
 
     /// Mutate organism
     pub fn mutate(&mut self) {
-        self.genome.mutate();
+        let mut innovations = INNOVATIONS.lock().unwrap();
+        let (log, global_innovation) = &mut *innovations;
+
+        self.morphology.mutate(&mut self.genome);
+        self.genome
+            .mutate(&MutationProfile::mcc(), log, global_innovation);
+
+        if config::AGENT.simplify_children {
+            self.genome.simplify();
+        }
+        self.network = OnceLock::new();
+        self.refinement_evaluations = 0;
+        self.refined = false;
+    }
+
+    /// Replaces the genome with one of refined weights. The agent gets a new id,
+    /// as outcomes memoized for the old weights no longer apply.
+    pub(crate) fn set_refined_genome(&mut self, genome: Genome) {
+        self.genome = genome;
+        self.network = OnceLock::new();
+        self.refined = true;
+        self.id = next_individual_id();
     }
 
     pub fn distance(&self, other: &Self) -> f64 {
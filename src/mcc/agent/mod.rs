@@ -1,6 +1,4 @@
-mod agent_genome;
 pub mod agent_queue;
 pub mod agent_species;
 pub mod mcc_agent;
-mod neural_network;
 pub mod speciated_agent_queue;
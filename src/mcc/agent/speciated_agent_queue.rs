@@ -1,11 +1,13 @@
 use crate::config;
 use crate::mcc::agent::agent_species::AgentSpecies;
 use crate::mcc::agent::mcc_agent::MCCAgent;
+use crate::mcc::species::{assign_species, nearest, split_capacity, Assignment};
 use crate::neatns::agent::Agent;
 
 pub struct SpeciatedAgentQueue {
     pub(crate) species: Vec<AgentSpecies>,
     pub species_added: u32,
+    assignments: Vec<Assignment>, // Kept until taken by the analyzer
 }
 
 impl SpeciatedAgentQueue {
@@ -20,6 +22,7 @@ impl SpeciatedAgentQueue {
         let mut queue = SpeciatedAgentQueue {
             species: vec![],
             species_added: 0,
+            assignments: vec![],
         };
 
         let base_amount: u32 = config::MCC.agent_population_capacity / mcc_agents.len() as u32;
@@ -62,31 +65,60 @@ impl SpeciatedAgentQueue {
         length
     }
 
-    // Looks for suitable species to put new agent in
-    // If none are found, a new species is made
+    // Puts the agent into the species chosen by the configured assignment policy.
+    // If no species is close enough, the agent founds one with half the capacity of the
+    // largest species, unless every selected child already needs a species of its own.
     pub fn push(&mut self, agent: MCCAgent) {
-        let mut distances: Vec<f64> = vec![];
-
-        for species in self.species.iter() {
-            distances.push(species.distance(&agent));
-        }
-
-        let mut highest = 0.0;
-        let mut index: usize = 0;
+        let distances: Vec<f64> = self.species.iter().map(|s| s.distance(&agent)).collect();
+        let nearest_distance = distances[nearest(&distances)];
+        let threshold = config::MCC.agent_species_assignment_threshold;
+
+        let donor = (0..self.species.len())
+            .max_by_key(|i| self.species[*i].agent_queue.max_items_limit)
+            .unwrap();
+        let founding_capacities =
+            split_capacity(self.species[donor].agent_queue.max_items_limit, 1, 1)
+                .filter(|_| self.species.len() < config::MCC.agent_selection_limit);
+
+        let (index, founded) = match (assign_species(&distances, threshold), founding_capacities) {
+            (None, Some((kept, given))) => {
+                self.species[donor].agent_queue.max_items_limit = kept;
+
+                let species = AgentSpecies::new(agent, given, self.species_added);
+                self.species.push(species);
+                self.species_added += 1;
+
+                (self.species.len() - 1, true)
+            }
+            (chosen, _) => {
+                let index = chosen.unwrap_or_else(|| nearest(&distances));
+                self.species[index].push(agent);
 
-        for (i, value) in distances.iter().enumerate() {
-            if *value > highest {
-                highest = *value;
-                index = i;
+                (index, false)
             }
-        }
+        };
 
-        self.species[index].push(agent);
+        self.assignments.push(Assignment {
+            species: self.species[index].id,
+            distance: if founded {
+                nearest_distance
+            } else {
+                distances[index]
+            },
+            nearest: nearest_distance,
+            founded,
+            species_amount: self.species.len(),
+        });
+    }
+
+    /// Assignments made since the last call
+    pub fn take_assignments(&mut self) -> Vec<Assignment> {
+        std::mem::take(&mut self.assignments)
     }
 
     // Generates children from the next parents in line
     // Picks parents from all species
-    // The parents are only mutated, no crossover is performed
+    // Crossover partners are picked from the same species
     pub fn get_children(&mut self) -> Vec<MCCAgent> {
         let mut children: Vec<MCCAgent> = vec![];
 
@@ -140,6 +172,16 @@ impl SpeciatedAgentQueue {
         sum as f64 / self.len() as f64
     }
 
+    /// Average of a count taken from every agent, such as its functional size
+    pub fn get_average_of<F>(&self, metric: F) -> f64
+    where
+        F: Fn(&MCCAgent) -> usize,
+    {
+        let sum: usize = self.iter_individuals().map(metric).sum();
+
+        sum as f64 / self.len() as f64
+    }
+
     pub fn get_overall_average_size_increase(&self) -> f64 {
         let mut sum = 0.0;
         for s in self.species.iter() {
@@ -158,6 +200,61 @@ impl SpeciatedAgentQueue {
         sum as f64 / self.species.len() as f64
     }
 
+    // Updates the centroids, merges species that have converged and splits species that
+    // have spread out. Splits stop when every selected child would need its own species.
+    pub fn update_species(&mut self) {
+        for species in self.species.iter_mut() {
+            species.update_centroid();
+        }
+
+        let merge_distance = config::MCC.agent_species_merge_distance;
+        let mut index = 0;
+        while merge_distance > 0.0 && index < self.species.len() {
+            let close = (0..self.species.len()).find(|other| {
+                *other != index
+                    && self.species[index].species_distance(&self.species[*other]) < merge_distance
+            });
+
+            if let Some(other) = close {
+                // The larger species takes in the smaller one
+                let (keep, absorb) = if self.species[index].len() >= self.species[other].len() {
+                    (index, other)
+                } else {
+                    (other, index)
+                };
+
+                let absorbed = self.species.remove(absorb);
+                let keep = if absorb < keep { keep - 1 } else { keep };
+                println!(
+                    "Agent species {} merged into species {}",
+                    absorbed.id, self.species[keep].id
+                );
+                self.species[keep].merge(absorbed);
+            } else {
+                index += 1;
+            }
+        }
+
+        let split_distance = config::MCC.agent_species_split_distance;
+        let mut index = 0;
+        while split_distance > 0.0
+            && index < self.species.len()
+            && self.species.len() < config::MCC.agent_selection_limit
+        {
+            if self.species[index].spread() > split_distance {
+                if let Some(species) = self.species[index].split(self.species_added) {
+                    println!(
+                        "Agent species {} split off species {}",
+                        self.species[index].id, species.id
+                    );
+                    self.species.push(species);
+                    self.species_added += 1;
+                }
+            }
+            index += 1;
+        }
+    }
+
     pub fn save_state(&mut self) {
         for s in self.species.iter_mut() {
             s.save_state();
@@ -1,3 +1,4 @@
+pub mod multi_agent;
 pub mod replacement_controller;
 pub mod species_replacement;
 pub mod varied_size;
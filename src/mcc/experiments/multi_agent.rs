@@ -0,0 +1,22 @@
+use crate::analytics::Analyzer;
+use crate::mcc::evolve_speciated;
+use crate::simulator::morphology::Morphology;
+use crate::simulator::multi_agent::simulate_many_multi;
+
+// Speciated MCC where agent children are evaluated in groups sharing each maze.
+// Reports that replay single agent runs, such as diversity, robustness and efficiency, are left out,
+// as an agent may only have solved its maze while sharing it with its group.
+pub fn run_multi_agent_experiment(analyzer: &mut Analyzer) {
+    println!("Running multi-agent experiment");
+
+    evolve_speciated(
+        analyzer,
+        &Morphology::with_agent_sensors(),
+        |agents, mazes| simulate_many_multi(agents, mazes),
+    );
+
+    analyzer.generate_seed_search_report();
+    analyzer.generate_reproduction_report();
+    analyzer.generate_assignment_report();
+    analyzer.generate_throughput_report();
+}
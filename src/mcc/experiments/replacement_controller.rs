@@ -6,6 +6,7 @@ use crate::mcc::agent::speciated_agent_queue::SpeciatedAgentQueue;
 use crate::mcc::maze::maze_species::MazeSpecies;
 use crate::mcc::maze::speciated_maze_queue::SpeciatedMazeQueue;
 use crate::neatns;
+use crate::simulator::morphology::Morphology;
 
 pub struct ReplacementController {}
 
@@ -39,7 +40,7 @@ impl ReplacementController {
             empty_agents.len()
         };
 
-        let seeds = neatns::generate_seeds(seed_pair_amount as u32, false);
+        let seeds = neatns::generate_seeds(seed_pair_amount as u32, false, &Morphology::new());
 
         for i in 0..seed_pair_amount {
             let maze_index = mazes.species.iter().position(|m| m.id == empty_mazes[i].id);
@@ -95,7 +96,7 @@ impl ReplacementController {
         let agent_amount = empty_agent.unwrap().agent_queue.max_items_limit;
 
         if small_maze.is_some() {
-            let agent = neatns::find_agent_seed_for_maze(small_maze.unwrap());
+            let agent = neatns::find_agent_seed_for_maze(small_maze.unwrap(), &Morphology::new());
 
             let agent_index = agents
                 .species
@@ -1,16 +1,21 @@
+use std::time::Instant;
+
 use crate::analytics::Analyzer;
 use crate::config;
 use crate::mcc::agent::speciated_agent_queue::SpeciatedAgentQueue;
 use crate::mcc::experiments::replacement_controller::ReplacementController;
 use crate::mcc::maze::speciated_maze_queue::SpeciatedMazeQueue;
-use crate::mcc::{generate_generation_stats_s};
+use crate::mcc::{generate_generation_stats_s, update_species};
 use crate::neatns;
+use crate::simulator::memo::clear_results;
+use crate::simulator::morphology::Morphology;
 use crate::simulator::simulate_many;
 
 pub fn run_replacement_experiment(analyzer: &mut Analyzer) {
     println!("Running sudden replacement experiment");
 
-    let seeds = neatns::generate_seeds(config::MCC.maze_seed_amount, true);
+    clear_results();
+    let seeds = neatns::generate_seeds(config::MCC.maze_seed_amount, true, &Morphology::new());
 
     let mut agents = SpeciatedAgentQueue::new(seeds.agents);
     let mut mazes = SpeciatedMazeQueue::new(seeds.mazes);
@@ -25,7 +30,13 @@ pub fn run_replacement_experiment(analyzer: &mut Analyzer) {
         let mut agent_children = agents.get_children();
         let mut maze_children = mazes.get_children();
 
+        let simulation_start = Instant::now();
         simulate_many(&mut agent_children, &mut maze_children);
+        analyzer.add_simulation_time(
+            agent_children.len() * maze_children.len(),
+            simulation_start.elapsed(),
+        );
+        analyzer.add_reproduction_stats(&agent_children);
 
         for child in agent_children.iter() {
             if child.viable {
@@ -39,6 +50,9 @@ pub fn run_replacement_experiment(analyzer: &mut Analyzer) {
             }
         }
 
+        update_species(generation, &mut agents, &mut mazes);
+        analyzer.add_assignment_stats(generation, &mut agents, &mut mazes);
+
         let generation_stats = generate_generation_stats_s(generation as u32, &agents, &mazes);
         analyzer.add_generation_stats(&generation_stats);
 
@@ -59,5 +73,13 @@ pub fn run_replacement_experiment(analyzer: &mut Analyzer) {
         }
     }
     analyzer.generate_diversity_score(&agents, &mazes);
+    analyzer.generate_robustness_score(&agents, &mazes);
+    analyzer.generate_efficiency_score(&agents, &mazes);
+    analyzer.export_traces(&mazes, &agents);
+    analyzer.generate_seed_search_report();
+    analyzer.generate_reproduction_report();
+    analyzer.generate_refinement_report();
+    analyzer.generate_assignment_report();
+    analyzer.generate_throughput_report();
     analyzer.visualize_trajectories(&mazes, &agents);
 }
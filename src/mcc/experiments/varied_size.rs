@@ -1,3 +1,5 @@
+use std::time::Instant;
+
 use crate::analytics::Analyzer;
 use crate::config;
 use crate::mcc::agent::speciated_agent_queue::SpeciatedAgentQueue;
@@ -5,6 +7,8 @@ use crate::mcc::agent::speciated_agent_queue::SpeciatedAgentQueue;
 use crate::mcc::generate_generation_stats_s;
 use crate::mcc::maze::speciated_maze_queue::SpeciatedMazeQueue;
 use crate::neatns;
+use crate::simulator::memo::clear_results;
+use crate::simulator::morphology::Morphology;
 use crate::simulator::simulate_many;
 
 use crate::mcc::experiments::varied_size_controller::VariedSizeController;
@@ -12,7 +16,8 @@ use crate::mcc::experiments::varied_size_controller::VariedSizeController;
 pub fn run_varied_size_experiment(analyzer: &mut Analyzer) {
     println!("Running varied size experiment");
 
-    let seeds = neatns::generate_seeds(config::MCC.maze_seed_amount, true);
+    clear_results();
+    let seeds = neatns::generate_seeds(config::MCC.maze_seed_amount, true, &Morphology::new());
 
     let mut agents = SpeciatedAgentQueue::new(seeds.agents);
     let mut mazes = SpeciatedMazeQueue::new(seeds.mazes);
@@ -26,7 +31,13 @@ pub fn run_varied_size_experiment(analyzer: &mut Analyzer) {
         let mut agent_children = agents.get_children();
         let mut maze_children = mazes.get_children();
 
+        let simulation_start = Instant::now();
         simulate_many(&mut agent_children, &mut maze_children);
+        analyzer.add_simulation_time(
+            agent_children.len() * maze_children.len(),
+            simulation_start.elapsed(),
+        );
+        analyzer.add_reproduction_stats(&agent_children);
 
         for child in agent_children.iter() {
             if child.viable {
@@ -61,4 +72,11 @@ pub fn run_varied_size_experiment(analyzer: &mut Analyzer) {
         }
     }
     analyzer.generate_diversity_score(&agents, &mazes);
+    analyzer.generate_robustness_score(&agents, &mazes);
+    analyzer.generate_efficiency_score(&agents, &mazes);
+    analyzer.export_traces(&mazes, &agents);
+    analyzer.generate_seed_search_report();
+    analyzer.generate_reproduction_report();
+    analyzer.generate_refinement_report();
+    analyzer.generate_throughput_report();
 }
@@ -1,22 +1,19 @@
 use crate::maze::maze_genotype::MazeGenome;
+use crate::mcc::next_individual_id;
 
 #[derive(Debug, Clone)]
 pub struct MazeQueue {
     pub mazes: Vec<MazeGenome>,
     current_maze_index: usize,
     pub(crate) max_items_limit: u32,
-    total_individuals_added: u32,
 }
 
 impl MazeQueue {
     pub fn new(mazes: Vec<MazeGenome>, max_items_limit: u32) -> MazeQueue {
-        let total_individuals_added = mazes.len() as u32;
-
         MazeQueue {
             mazes,
             current_maze_index: 0,
             max_items_limit,
-            total_individuals_added,
         }
     }
 
@@ -61,9 +58,9 @@ impl MazeQueue {
 
         for child in children.iter_mut() {
             child.successful_agent_id = None;
+            child.successful_noise_seed = None;
             child.viable = false;
-            child.id = self.total_individuals_added;
-            self.total_individuals_added += 1;
+            child.id = next_individual_id();
             child.mutate();
         }
 
@@ -1,6 +1,7 @@
 use crate::config;
 use crate::maze::maze_genotype::MazeGenome;
 use crate::mcc::maze::maze_queue::MazeQueue;
+use crate::mcc::species::{medoid, partition, split_capacity, CentroidUpdate};
 
 #[derive(Debug, Clone)]
 pub struct MazeSpeciesStatistics {
@@ -100,7 +101,81 @@ impl MazeSpecies {
     }
 
     pub fn distance(&self, other: &MazeGenome) -> f64 {
-        self.centroid.distance(other)
+        match config::MCC.species_centroid {
+            CentroidUpdate::AverageLinkage => {
+                self.iter().map(|m| m.distance(other)).sum::<f64>() / self.len() as f64
+            }
+            _ => self.centroid.distance(other),
+        }
+    }
+
+    /// Distance between two species, from the members of 'other' to this species
+    pub fn species_distance(&self, other: &MazeSpecies) -> f64 {
+        match config::MCC.species_centroid {
+            CentroidUpdate::AverageLinkage => {
+                other.iter().map(|m| self.distance(m)).sum::<f64>() / other.len() as f64
+            }
+            _ => self.distance(&other.centroid),
+        }
+    }
+
+    /// Average distance of the members to the species
+    pub fn spread(&self) -> f64 {
+        self.iter().map(|m| self.distance(m)).sum::<f64>() / self.len() as f64
+    }
+
+    pub fn update_centroid(&mut self) {
+        if config::MCC.species_centroid == CentroidUpdate::Medoid {
+            let mazes: Vec<&MazeGenome> = self.iter().collect();
+            let index = medoid(mazes.len(), |i, j| mazes[i].distance(mazes[j]));
+
+            self.centroid = mazes[index].clone();
+        }
+    }
+
+    /// Splits off the members closer to a distant member than to the other side of the species.
+    /// Capacity is divided by the size of both parts, and the new species inherits the statistics.
+    /// A species with a capacity below two is not split, as one part would get no room.
+    pub fn split(&mut self, id: u32) -> Option<MazeSpecies> {
+        let mazes: Vec<&MazeGenome> = self.iter().collect();
+        let ((centroid, members), (new_centroid, new_members)) = partition(
+            mazes.len(),
+            2,
+            |i| self.distance(mazes[i]),
+            |i, j| mazes[i].distance(mazes[j]),
+        )?;
+
+        let (capacity, new_capacity) = split_capacity(
+            self.maze_queue.max_items_limit,
+            members.len(),
+            new_members.len(),
+        )?;
+        let collect = |indices: &[usize]| indices.iter().map(|i| mazes[*i].clone()).collect();
+
+        let mut new_species = MazeSpecies {
+            centroid: mazes[new_centroid].clone(),
+            maze_queue: MazeQueue::new(collect(&new_members), new_capacity),
+            id,
+            statistics: self.statistics.clone(),
+        };
+        let (centroid, members) = (mazes[centroid].clone(), collect(&members));
+
+        self.centroid = centroid;
+        self.maze_queue = MazeQueue::new(members, capacity);
+
+        self.update_centroid();
+        new_species.update_centroid();
+
+        Some(new_species)
+    }
+
+    /// Takes in all members and the capacity of another species
+    pub fn merge(&mut self, other: MazeSpecies) {
+        let mazes = self.iter().chain(other.iter()).cloned().collect();
+        let capacity = self.maze_queue.max_items_limit + other.maze_queue.max_items_limit;
+
+        self.maze_queue = MazeQueue::new(mazes, capacity);
+        self.update_centroid();
     }
 
     pub fn save_state(&mut self) {
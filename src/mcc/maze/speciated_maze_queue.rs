@@ -1,10 +1,12 @@
 use crate::config;
 use crate::maze::maze_genotype::MazeGenome;
 use crate::mcc::maze::maze_species::MazeSpecies;
+use crate::mcc::species::{assign_species, nearest, split_capacity, Assignment};
 
 pub struct SpeciatedMazeQueue {
     pub species: Vec<MazeSpecies>,
     pub species_added: u32,
+    assignments: Vec<Assignment>, // Kept until taken by the analyzer
 }
 
 impl SpeciatedMazeQueue {
@@ -12,6 +14,7 @@ impl SpeciatedMazeQueue {
         let mut queue = SpeciatedMazeQueue {
             species: vec![],
             species_added: 0,
+            assignments: vec![],
         };
 
         let species_max_mazes_limit: u32 =
@@ -48,24 +51,55 @@ impl SpeciatedMazeQueue {
         self.species.iter().map(|species| species.iter()).flatten()
     }
 
+    // Puts the maze into the species chosen by the configured assignment policy.
+    // If no species is close enough, the maze founds one with half the capacity of the
+    // largest species, unless every selected child already needs a species of its own.
     pub fn push(&mut self, maze: MazeGenome) {
-        let mut distances: Vec<f64> = vec![];
-
-        for species in self.species.iter() {
-            distances.push(species.distance(&maze));
-        }
-
-        let mut highest = 0.0;
-        let mut index: usize = 0;
+        let distances: Vec<f64> = self.species.iter().map(|s| s.distance(&maze)).collect();
+        let nearest_distance = distances[nearest(&distances)];
+        let threshold = config::MCC.maze_species_assignment_threshold;
+
+        let donor = (0..self.species.len())
+            .max_by_key(|i| self.species[*i].maze_queue.max_items_limit)
+            .unwrap();
+        let founding_capacities =
+            split_capacity(self.species[donor].maze_queue.max_items_limit, 1, 1)
+                .filter(|_| self.species.len() < config::MCC.maze_selection_limit);
+
+        let (index, founded) = match (assign_species(&distances, threshold), founding_capacities) {
+            (None, Some((kept, given))) => {
+                self.species[donor].maze_queue.max_items_limit = kept;
+
+                let species = MazeSpecies::new(maze, given, self.species_added);
+                self.species.push(species);
+                self.species_added += 1;
+
+                (self.species.len() - 1, true)
+            }
+            (chosen, _) => {
+                let index = chosen.unwrap_or_else(|| nearest(&distances));
+                self.species[index].push(maze);
 
-        for (i, value) in distances.iter().enumerate() {
-            if *value > highest {
-                highest = *value;
-                index = i;
+                (index, false)
             }
-        }
+        };
 
-        self.species[index].push(maze);
+        self.assignments.push(Assignment {
+            species: self.species[index].id,
+            distance: if founded {
+                nearest_distance
+            } else {
+                distances[index]
+            },
+            nearest: nearest_distance,
+            founded,
+            species_amount: self.species.len(),
+        });
+    }
+
+    /// Assignments made since the last call
+    pub fn take_assignments(&mut self) -> Vec<Assignment> {
+        std::mem::take(&mut self.assignments)
     }
 
     pub fn get_children(&mut self) -> Vec<MazeGenome> {
@@ -195,6 +229,61 @@ impl SpeciatedMazeQueue {
         sum as f64 / self.species.len() as f64
     }
 
+    // Updates the centroids, merges species that have converged and splits species that
+    // have spread out. Splits stop when every selected child would need its own species.
+    pub fn update_species(&mut self) {
+        for species in self.species.iter_mut() {
+            species.update_centroid();
+        }
+
+        let merge_distance = config::MCC.maze_species_merge_distance;
+        let mut index = 0;
+        while merge_distance > 0.0 && index < self.species.len() {
+            let close = (0..self.species.len()).find(|other| {
+                *other != index
+                    && self.species[index].species_distance(&self.species[*other]) < merge_distance
+            });
+
+            if let Some(other) = close {
+                // The larger species takes in the smaller one
+                let (keep, absorb) = if self.species[index].len() >= self.species[other].len() {
+                    (index, other)
+                } else {
+                    (other, index)
+                };
+
+                let absorbed = self.species.remove(absorb);
+                let keep = if absorb < keep { keep - 1 } else { keep };
+                println!(
+                    "Maze species {} merged into species {}",
+                    absorbed.id, self.species[keep].id
+                );
+                self.species[keep].merge(absorbed);
+            } else {
+                index += 1;
+            }
+        }
+
+        let split_distance = config::MCC.maze_species_split_distance;
+        let mut index = 0;
+        while split_distance > 0.0
+            && index < self.species.len()
+            && self.species.len() < config::MCC.maze_selection_limit
+        {
+            if self.species[index].spread() > split_distance {
+                if let Some(species) = self.species[index].split(self.species_added) {
+                    println!(
+                        "Maze species {} split off species {}",
+                        self.species[index].id, species.id
+                    );
+                    self.species.push(species);
+                    self.species_added += 1;
+                }
+            }
+            index += 1;
+        }
+    }
+
     pub fn save_state(&mut self) {
         for s in self.species.iter_mut() {
             s.save_state();
@@ -1,21 +1,37 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Instant;
+
 use crate::analytics::{Analyzer, GenerationStatistics};
 use crate::config;
+use crate::maze::maze_genotype::MazeGenome;
 use crate::mcc::agent::agent_queue::AgentQueue;
 use crate::mcc::agent::mcc_agent::MCCAgent;
 use crate::mcc::agent::speciated_agent_queue::SpeciatedAgentQueue;
 use crate::mcc::maze::maze_queue::MazeQueue;
 use crate::mcc::maze::speciated_maze_queue::SpeciatedMazeQueue;
 use crate::neatns;
+use crate::simulator::memo::clear_results;
+use crate::simulator::morphology::Morphology;
 use crate::simulator::simulate_many;
 
 pub(crate) mod agent;
 pub mod experiments;
 pub mod maze;
+pub mod species;
+
+static NEXT_INDIVIDUAL_ID: AtomicU32 = AtomicU32::new(0);
+
+// Agents and mazes draw their ids from one counter, so an id is never shared between species
+// or batches. Simulation results are memoized by (agent id, maze id), which relies on this.
+pub fn next_individual_id() -> u32 {
+    NEXT_INDIVIDUAL_ID.fetch_add(1, Ordering::Relaxed)
+}
 
 pub fn run_regular_mcc(analyzer: &mut Analyzer) {
     println!("Running regular MCC with no speciation");
 
-    let seeds = neatns::generate_seeds(config::MCC.maze_seed_amount, true);
+    clear_results();
+    let seeds = neatns::generate_seeds(config::MCC.maze_seed_amount, true, &Morphology::new());
 
     let mcc_agents: Vec<MCCAgent> = seeds
         .agents
@@ -30,7 +46,13 @@ pub fn run_regular_mcc(analyzer: &mut Analyzer) {
         let mut agent_children = agents.get_children(config::MCC.agent_selection_limit);
         let mut maze_children = mazes.get_children(config::MCC.maze_selection_limit);
 
+        let simulation_start = Instant::now();
         simulate_many(&mut agent_children, &mut maze_children);
+        analyzer.add_simulation_time(
+            agent_children.len() * maze_children.len(),
+            simulation_start.elapsed(),
+        );
+        analyzer.add_reproduction_stats(&agent_children);
 
         for child in agent_children.iter() {
             if child.viable {
@@ -55,12 +77,43 @@ pub fn run_regular_mcc(analyzer: &mut Analyzer) {
         }*/
     }
     analyzer.generate_diversity_score_no_species(&agents, &mazes);
+    analyzer.generate_robustness_score_no_species(&agents, &mazes);
+    analyzer.generate_efficiency_score_no_species(&agents, &mazes);
+    analyzer.export_traces_no_species(&mazes, &agents);
+    analyzer.generate_seed_search_report();
+    analyzer.generate_reproduction_report();
+    analyzer.generate_refinement_report();
+    analyzer.generate_throughput_report();
 }
 
 pub fn run_regular_speciated_mcc(analyzer: &mut Analyzer) {
     println!("Running regular MCC with speciation");
 
-    let seeds = neatns::generate_seeds(config::MCC.maze_seed_amount, true);
+    let (agents, mazes) = evolve_speciated(analyzer, &Morphology::new(), simulate_many);
+
+    analyzer.generate_diversity_score(&agents, &mazes);
+    analyzer.generate_robustness_score(&agents, &mazes);
+    analyzer.generate_efficiency_score(&agents, &mazes);
+    analyzer.export_traces(&mazes, &agents);
+    analyzer.generate_seed_search_report();
+    analyzer.generate_reproduction_report();
+    analyzer.generate_refinement_report();
+    analyzer.generate_assignment_report();
+    analyzer.generate_throughput_report();
+}
+
+// Runs speciated MCC from seeds with the given sensor layout, every generation's children
+// are evaluated with 'evaluate'. Returns the final populations for the reports of the caller.
+pub fn evolve_speciated<F>(
+    analyzer: &mut Analyzer,
+    morphology: &Morphology,
+    evaluate: F,
+) -> (SpeciatedAgentQueue, SpeciatedMazeQueue)
+where
+    F: Fn(&mut Vec<MCCAgent>, &mut Vec<MazeGenome>),
+{
+    clear_results();
+    let seeds = neatns::generate_seeds(config::MCC.maze_seed_amount, true, morphology);
 
     let mut agents = SpeciatedAgentQueue::new(seeds.agents);
     let mut mazes = SpeciatedMazeQueue::new(seeds.mazes);
@@ -72,7 +125,13 @@ pub fn run_regular_speciated_mcc(analyzer: &mut Analyzer) {
         let mut agent_children = agents.get_children();
         let mut maze_children = mazes.get_children();
 
-        simulate_many(&mut agent_children, &mut maze_children);
+        let simulation_start = Instant::now();
+        evaluate(&mut agent_children, &mut maze_children);
+        analyzer.add_simulation_time(
+            agent_children.len() * maze_children.len(),
+            simulation_start.elapsed(),
+        );
+        analyzer.add_reproduction_stats(&agent_children);
 
         for child in agent_children.iter() {
             if child.viable {
@@ -86,6 +145,9 @@ pub fn run_regular_speciated_mcc(analyzer: &mut Analyzer) {
             }
         }
 
+        update_species(generation, &mut agents, &mut mazes);
+        analyzer.add_assignment_stats(generation, &mut agents, &mut mazes);
+
         let generation_stats = generate_generation_stats_s(generation as u32, &agents, &mazes);
         analyzer.add_generation_stats(&generation_stats);
 
@@ -101,7 +163,21 @@ pub fn run_regular_speciated_mcc(analyzer: &mut Analyzer) {
         }
     }
 
-    analyzer.generate_diversity_score(&agents, &mazes);
+    (agents, mazes)
+}
+
+// Updates centroids, merges and splits species of both populations every few generations
+pub fn update_species(
+    generation: usize,
+    agents: &mut SpeciatedAgentQueue,
+    mazes: &mut SpeciatedMazeQueue,
+) {
+    let interval = config::MCC.species_update_interval;
+
+    if generation != 0 && generation.is_multiple_of(interval) {
+        agents.update_species();
+        mazes.update_species();
+    }
 }
 
 #[allow(dead_code)]
@@ -128,11 +204,13 @@ pub fn print_stats(agents: &SpeciatedAgentQueue, mazes: &SpeciatedMazeQueue) {
 
     for (_, s) in agents.iter_species().enumerate() {
         println!(
-            "Agent species {}: {}/{}\t| Avg size: {:.2} | Avg size increase: {:.2}",
+            "Agent species {}: {}/{}\t| Avg size: {:.2} | Avg functional size: {:.2} | Avg sensors: {:.2} | Avg size increase: {:.2}",
             s.id,
             s.agent_queue.len(),
             s.agent_queue.max_items_limit,
             s.agent_queue.get_average_size(),
+            s.agent_queue.get_average_of(functional_size),
+            s.agent_queue.get_average_of(sensor_amount),
             s.statistics.get_current_average_size_increase()
         );
     }
@@ -150,6 +228,16 @@ pub fn print_stats(agents: &SpeciatedAgentQueue, mazes: &SpeciatedMazeQueue) {
     }
 }
 
+// Links that can affect an output
+fn functional_size(agent: &MCCAgent) -> usize {
+    agent.genome.functional_size()
+}
+
+// Network inputs fed by rangefinders, radar and agent sensors
+fn sensor_amount(agent: &MCCAgent) -> usize {
+    agent.morphology.sensor_amount()
+}
+
 /*fn generate_generation_stats(
     generation: u32,
     agents: &AgentQueue,
@@ -166,6 +254,8 @@ pub fn print_stats(agents: &SpeciatedAgentQueue, mazes: &SpeciatedMazeQueue) {
         agents.get_average_size(),
         agents.get_largest_size(),
         agents.get_smallest_size(),
+        agents.get_average_of(functional_size),
+        agents.get_average_of(sensor_amount),
     )
 }*/
 
@@ -193,5 +283,7 @@ pub fn generate_generation_stats_s(
         agents.get_overall_average_size_increase(),
         mazes.get_overall_average_size_increase(),
         mazes.get_overall_average_complexity_increase(),
+        agents.get_average_of(functional_size),
+        agents.get_average_of(sensor_amount),
     )
 }
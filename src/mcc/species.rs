@@ -0,0 +1,231 @@
+use std::str;
+
+use rand::Rng;
+
+use crate::config;
+
+/// What the members of an MCC species are compared against
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CentroidUpdate {
+    Fixed,  // The founder, replaced only when the species splits
+    Medoid, // The member with the lowest summed distance to the others, updated periodically
+    // No centroid, the distance to a species is the mean distance to all its members.
+    // Every distance to the species then costs one genome distance per member.
+    AverageLinkage,
+}
+
+/// How speciated queues choose the species of a newcomer
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpeciesAssignment {
+    Farthest,      // The species at the highest distance, the original behaviour
+    Nearest,       // The species at the lowest distance
+    Threshold,     // The nearest species within the threshold, or else a new species
+    Probabilistic, // Any species, with a chance proportional to 1 / (1 + distance)
+}
+
+/// Where a speciated queue put a newcomer, kept for the assignment report
+#[derive(Debug, Clone)]
+pub struct Assignment {
+    pub species: u32,          // Id of the species the newcomer was put in
+    pub distance: f64,         // Distance to that species, or to the nearest one when founding
+    pub nearest: f64,          // Distance to the nearest species
+    pub founded: bool,         // The newcomer founded a new species
+    pub species_amount: usize, // Species in the queue after the assignment
+}
+
+impl Assignment {
+    pub fn to_whitespace_separated_string(&self) -> String {
+        format!(
+            "{} {:.5} {:.5} {} {}",
+            self.species, self.distance, self.nearest, self.founded as u8, self.species_amount
+        )
+    }
+}
+
+/// Index of the species at the lowest distance
+pub fn nearest(distances: &[f64]) -> usize {
+    distances
+        .iter()
+        .enumerate()
+        .min_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+        .map_or(0, |(i, _)| i)
+}
+
+/// Index of the species a newcomer at 'distances' from every species goes into under the
+/// configured policy, or None if it should found a new species
+pub fn assign_species(distances: &[f64], threshold: f64) -> Option<usize> {
+    match config::MCC.species_assignment {
+        SpeciesAssignment::Farthest => {
+            let mut highest = 0.0;
+            let mut index: usize = 0;
+
+            for (i, value) in distances.iter().enumerate() {
+                if *value > highest {
+                    highest = *value;
+                    index = i;
+                }
+            }
+
+            Some(index)
+        }
+        SpeciesAssignment::Nearest => Some(nearest(distances)),
+        SpeciesAssignment::Threshold => {
+            let index = nearest(distances);
+            distances
+                .get(index)
+                .filter(|distance| **distance <= threshold)
+                .map(|_| index)
+        }
+        SpeciesAssignment::Probabilistic => {
+            let similarities: Vec<f64> = distances.iter().map(|d| 1.0 / (1.0 + d)).collect();
+            let mut target = rand::thread_rng().gen::<f64>() * similarities.iter().sum::<f64>();
+
+            for (i, similarity) in similarities.iter().enumerate() {
+                if target < *similarity {
+                    return Some(i);
+                }
+                target -= similarity;
+            }
+
+            Some(distances.len().saturating_sub(1))
+        }
+    }
+}
+
+// Founder of a group of members, and the indices of all members of the group
+type Group = (usize, Vec<usize>);
+
+/// Index of the member with the lowest summed distance to all other members.
+/// 'distance' takes the indices of two members.
+pub fn medoid<F>(amount: usize, distance: F) -> usize
+where
+    F: Fn(usize, usize) -> f64,
+{
+    (0..amount)
+        .map(|i| (i, (0..amount).map(|j| distance(i, j)).sum::<f64>()))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map_or(0, |(i, _)| i)
+}
+
+/// Divides the members around two distant members, the one furthest from the species and
+/// the one furthest from that. Returns the founder and the members of both groups, with
+/// members in their original order, or None if either group would be smaller than 'min_size'.
+pub fn partition<F, G>(
+    amount: usize,
+    min_size: usize,
+    distance_to_species: F,
+    distance: G,
+) -> Option<(Group, Group)>
+where
+    F: Fn(usize) -> f64,
+    G: Fn(usize, usize) -> f64,
+{
+    let furthest = |distances: Vec<f64>| {
+        distances
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .map(|(i, _)| i)
+    };
+
+    let first = furthest((0..amount).map(&distance_to_species).collect())?;
+    let second = furthest((0..amount).map(|i| distance(first, i)).collect())?;
+
+    if first == second {
+        return None;
+    }
+
+    let (first_group, second_group): (Vec<usize>, Vec<usize>) = (0..amount).partition(|i| {
+        *i != second && (*i == first || distance(first, *i) <= distance(second, *i))
+    });
+
+    if first_group.len() < min_size || second_group.len() < min_size {
+        return None;
+    }
+
+    Some(((first, first_group), (second, second_group)))
+}
+
+/// Divides the capacity of a species between two parts, in proportion to their sizes.
+/// Returns None if the capacity can not give both parts room for at least one member.
+pub fn split_capacity(capacity: u32, first_size: usize, second_size: usize) -> Option<(u32, u32)> {
+    if capacity < 2 {
+        return None;
+    }
+
+    let second =
+        ((capacity as f64 * second_size as f64 / (first_size + second_size) as f64).round() as u32)
+            .max(1)
+            .min(capacity - 1);
+
+    Some((capacity - second, second))
+}
+
+impl str::FromStr for SpeciesAssignment {
+    type Err = u64;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Farthest" => Ok(SpeciesAssignment::Farthest),
+            "Nearest" => Ok(SpeciesAssignment::Nearest),
+            "Threshold" => Ok(SpeciesAssignment::Threshold),
+            "Probabilistic" => Ok(SpeciesAssignment::Probabilistic),
+            _ => Err(1),
+        }
+    }
+}
+
+impl str::FromStr for CentroidUpdate {
+    type Err = u64;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Fixed" => Ok(CentroidUpdate::Fixed),
+            "Medoid" => Ok(CentroidUpdate::Medoid),
+            "AverageLinkage" => Ok(CentroidUpdate::AverageLinkage),
+            _ => Err(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distance(points: &[f64]) -> impl Fn(usize, usize) -> f64 + '_ {
+        move |i, j| (points[i] - points[j]).abs()
+    }
+
+    #[test]
+    fn partition_separates_clusters() {
+        let points = [0.0, 10.0, 0.5, 10.5, 1.0, 9.5];
+        let ((first, first_group), (second, second_group)) =
+            partition(points.len(), 2, |i| points[i].abs(), distance(&points)).unwrap();
+
+        assert_eq!(first, 3);
+        assert_eq!(first_group, vec![1, 3, 5]);
+        assert_eq!(second, 0);
+        assert_eq!(second_group, vec![0, 2, 4]);
+        assert_eq!(medoid(points.len(), distance(&points)), 4);
+    }
+
+    #[test]
+    fn partition_respects_min_size() {
+        let points = [0.0, 0.5, 1.0, 10.0];
+
+        assert!(partition(points.len(), 2, |i| points[i], distance(&points)).is_none());
+        assert!(partition(points.len(), 1, |i| points[i], distance(&points)).is_some());
+    }
+
+    #[test]
+    fn capacity_is_split_by_size() {
+        assert_eq!(split_capacity(25, 3, 2), Some((15, 10)));
+        assert_eq!(split_capacity(2, 10, 1), Some((1, 1)));
+    }
+
+    #[test]
+    fn capacity_of_one_is_not_split() {
+        assert_eq!(split_capacity(1, 3, 2), None);
+        assert_eq!(split_capacity(0, 1, 1), None);
+    }
+}
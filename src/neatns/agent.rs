@@ -1,7 +1,10 @@
 use crate::neatns::network::genome::Genome;
 use crate::neatns::network::innovation::InnovationLog;
 use crate::neatns::network::innovation::InnovationTime;
+use crate::neatns::network::mutation::MutationProfile;
 use crate::neatns::network::neural_network::NeuralNetwork;
+use crate::neatns::network::substrate;
+use crate::simulator::morphology::Morphology;
 use crate::simulator::Point;
 use core::fmt;
 
@@ -13,10 +16,13 @@ pub struct Agent {
     pub adjusted_fitness: f64,
     pub generation: u64,
     pub final_position: Option<Point>,
+    pub morphology: Morphology, // Sensor layout the genome was sized for
 }
 
 impl Agent {
-    pub fn new(generation: u64, inputs: usize, outputs: usize, id: u32) -> Agent {
+    pub fn new(generation: u64, morphology: &Morphology, id: u32) -> Agent {
+        let (inputs, outputs) = substrate::genome_size(morphology);
+
         Agent {
             id,
             genome: Genome::new(inputs, outputs),
@@ -24,6 +30,7 @@ impl Agent {
             adjusted_fitness: 0.0,
             generation,
             final_position: Option::None,
+            morphology: morphology.clone(),
         }
     }
 
@@ -38,6 +45,7 @@ impl Agent {
             adjusted_fitness: 0.0,
             generation: self.generation + 1,
             final_position: Option::None,
+            morphology: self.morphology.clone(),
         }
     }
 
@@ -47,12 +55,13 @@ impl Agent {
     }*/
 
     pub fn to_phenotype(&self) -> NeuralNetwork {
-        NeuralNetwork::new(&self.genome)
+        substrate::build_network(&self.genome, &self.morphology)
     }
 
     /// Mutate organism
     pub fn mutate(&mut self, log: &mut InnovationLog, global_innovation: &mut InnovationTime) {
-        self.genome.mutate(log, global_innovation);
+        self.genome
+            .mutate(&MutationProfile::neatns(), log, global_innovation);
     }
 
     /// Genetic distance to other organism
@@ -0,0 +1,217 @@
+use std::str;
+
+use crate::config;
+use crate::maze::maze_phenotype::MazePhenotype;
+use crate::neatns::novelty_archive::euclidean_distance;
+use crate::simulator::{Point, SimulatorResult};
+
+/// Which behaviour characterization novelty is measured in
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BehaviourType {
+    FinalPosition,
+    Trajectory,
+    CellVisits,
+    FinalPositionTime,
+}
+
+/// Characterization of what an agent did during a simulation, compared by novelty search.
+/// Every behaviour has a position used for spatial indexing, and the distance between two
+/// behaviours is never smaller than 'position_bound' times the distance between their positions.
+pub trait Behaviour: Clone + Send {
+    /// Whether the simulation has to record the path of the agent
+    const NEEDS_PATH: bool;
+
+    fn from_result(
+        result: &SimulatorResult,
+        maze: &MazePhenotype,
+        step_budget: u32,
+    ) -> Option<Self>;
+
+    fn position(&self) -> &Point;
+
+    /// 0.0 when position gives no bound, which makes neighbour queries visit every behaviour
+    fn position_bound(&self) -> f64;
+
+    fn distance(&self, other: &Self) -> f64;
+}
+
+/// Where the agent ended up
+#[derive(Clone, Debug)]
+pub struct FinalPosition {
+    position: Point,
+}
+
+impl FinalPosition {
+    pub fn new(position: Point) -> FinalPosition {
+        FinalPosition { position }
+    }
+}
+
+impl Behaviour for FinalPosition {
+    const NEEDS_PATH: bool = false;
+
+    fn from_result(result: &SimulatorResult, _: &MazePhenotype, _: u32) -> Option<Self> {
+        result.final_position.clone().map(FinalPosition::new)
+    }
+
+    fn position(&self) -> &Point {
+        &self.position
+    }
+
+    fn position_bound(&self) -> f64 {
+        1.0
+    }
+
+    fn distance(&self, other: &Self) -> f64 {
+        euclidean_distance(&self.position, &other.position)
+    }
+}
+
+/// Positions sampled every 'trajectory_interval' steps of the step budget, ending with the final
+/// position. Agents that stop early keep their final position for the remaining samples.
+#[derive(Clone, Debug)]
+pub struct Trajectory {
+    samples: Vec<Point>,
+}
+
+impl Behaviour for Trajectory {
+    const NEEDS_PATH: bool = true;
+
+    fn from_result(result: &SimulatorResult, _: &MazePhenotype, step_budget: u32) -> Option<Self> {
+        let final_position = result.final_position.as_ref()?;
+        let interval = config::NEATNS.trajectory_interval.max(1) as usize;
+        let sample_amount = step_budget as usize / interval + 1;
+
+        let mut samples: Vec<Point> = (1..sample_amount)
+            .map(|i| {
+                result
+                    .agent_path
+                    .get(i * interval - 1)
+                    .unwrap_or(final_position)
+                    .clone()
+            })
+            .collect();
+        samples.push(final_position.clone());
+
+        Some(Trajectory { samples })
+    }
+
+    fn position(&self) -> &Point {
+        self.samples.last().unwrap()
+    }
+
+    fn position_bound(&self) -> f64 {
+        1.0 / self.samples.len() as f64
+    }
+
+    /// Mean distance between samples taken at the same step
+    fn distance(&self, other: &Self) -> f64 {
+        self.samples
+            .iter()
+            .zip(other.samples.iter())
+            .map(|(a, b)| euclidean_distance(a, b))
+            .sum::<f64>()
+            / self.samples.len().max(other.samples.len()) as f64
+    }
+}
+
+/// Share of steps spent in each maze cell
+#[derive(Clone, Debug)]
+pub struct CellVisits {
+    final_position: Point,
+    visits: Vec<f64>,
+}
+
+impl Behaviour for CellVisits {
+    const NEEDS_PATH: bool = true;
+
+    fn from_result(result: &SimulatorResult, maze: &MazePhenotype, _: u32) -> Option<Self> {
+        let final_position = result.final_position.as_ref()?;
+        let (width, height) = (maze.width as usize, maze.height as usize);
+        let mut visits = vec![0.0; width * height];
+        let share = 1.0 / result.agent_path.len().max(1) as f64;
+
+        for point in result.agent_path.iter() {
+            let x = (point.x.max(0.0) as usize).min(width - 1);
+            let y = (point.y.max(0.0) as usize).min(height - 1);
+            visits[y * width + x] += share;
+        }
+
+        Some(CellVisits {
+            final_position: final_position.clone(),
+            visits,
+        })
+    }
+
+    fn position(&self) -> &Point {
+        &self.final_position
+    }
+
+    fn position_bound(&self) -> f64 {
+        0.0
+    }
+
+    /// Total variation distance between the two distributions
+    fn distance(&self, other: &Self) -> f64 {
+        self.visits
+            .iter()
+            .zip(other.visits.iter())
+            .map(|(a, b)| (a - b).abs())
+            .sum::<f64>()
+            / 2.0
+    }
+}
+
+/// Final position and the share of the step budget used to get there,
+/// time is scaled by the maze width to weigh it like a position across the maze
+#[derive(Clone, Debug)]
+pub struct FinalPositionTime {
+    position: Point,
+    time: f64,
+}
+
+impl Behaviour for FinalPositionTime {
+    const NEEDS_PATH: bool = false;
+
+    fn from_result(
+        result: &SimulatorResult,
+        maze: &MazePhenotype,
+        step_budget: u32,
+    ) -> Option<Self> {
+        result
+            .final_position
+            .as_ref()
+            .map(|position| FinalPositionTime {
+                position: position.clone(),
+                time: result.steps_used as f64 / step_budget.max(1) as f64 * maze.width as f64,
+            })
+    }
+
+    fn position(&self) -> &Point {
+        &self.position
+    }
+
+    fn position_bound(&self) -> f64 {
+        1.0
+    }
+
+    fn distance(&self, other: &Self) -> f64 {
+        (euclidean_distance(&self.position, &other.position).powi(2)
+            + (self.time - other.time).powi(2))
+        .sqrt()
+    }
+}
+
+impl str::FromStr for BehaviourType {
+    type Err = u64;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "FinalPosition" => Ok(BehaviourType::FinalPosition),
+            "Trajectory" => Ok(BehaviourType::Trajectory),
+            "CellVisits" => Ok(BehaviourType::CellVisits),
+            "FinalPositionTime" => Ok(BehaviourType::FinalPositionTime),
+            _ => Err(1),
+        }
+    }
+}
@@ -2,13 +2,23 @@ use std::thread;
 
 use crate::config;
 use crate::maze::maze_genotype::{generate_random_maze, MazeGenome};
+use crate::mcc::next_individual_id;
 use crate::neatns::agent::Agent;
+use crate::neatns::behaviour::{
+    Behaviour, BehaviourType, CellVisits, FinalPosition, FinalPositionTime, Trajectory,
+};
 use crate::neatns::population::Population;
+use crate::neatns::search::{record_seed_search, SeedSearch};
+use crate::simulator::morphology::Morphology;
+use crate::simulator::step_budget::get_step_budget;
 
 pub(crate) mod agent;
+pub(crate) mod behaviour;
 pub(crate) mod network;
 pub(crate) mod novelty_archive;
 mod population;
+pub(crate) mod search;
+mod spatial_grid;
 mod species;
 
 pub struct Seeds {
@@ -20,48 +30,98 @@ impl Seeds {
     pub fn new(mazes: Vec<MazeGenome>, agents: Vec<Agent>) -> Seeds {
         Seeds { mazes, agents }
     }
+
+    // Seeds come from separate populations with overlapping ids, so they get new MCC ids.
+    // The first agents are paired with the mazes in the same order, which keeps the link.
+    fn assign_individual_ids(&mut self) {
+        for maze in self.mazes.iter_mut() {
+            maze.id = next_individual_id();
+        }
+
+        for (i, agent) in self.agents.iter_mut().enumerate() {
+            agent.id = next_individual_id();
+
+            if let Some(maze) = self.mazes.get_mut(i) {
+                maze.successful_agent_id = Some(agent.id);
+            }
+        }
+    }
+}
+
+/// Runs one seed search on the maze with the configured search mode and behaviour
+/// characterization, returns the first agent to solve it within the generation limit
+fn search_for_agent(maze: &MazeGenome, morphology: &Morphology) -> Option<Agent> {
+    match config::NEATNS.behaviour {
+        BehaviourType::FinalPosition => search::<FinalPosition>(maze, morphology),
+        BehaviourType::Trajectory => search::<Trajectory>(maze, morphology),
+        BehaviourType::CellVisits => search::<CellVisits>(maze, morphology),
+        BehaviourType::FinalPositionTime => search::<FinalPositionTime>(maze, morphology),
+    }
+}
+
+fn search<B: Behaviour>(maze: &MazeGenome, morphology: &Morphology) -> Option<Agent> {
+    let maze_phenotype = maze.get_phenotype();
+    let step_budget = get_step_budget(maze, maze_phenotype);
+
+    let mut population: Population<B> =
+        Population::new(config::NEAT.population_size, morphology);
+
+    let mut search = SeedSearch {
+        maze_width: maze_phenotype.width,
+        maze_height: maze_phenotype.height,
+        generations: config::MCC.find_seed_generation_limit,
+        solved: false,
+        speciation_history: vec![],
+    };
+
+    for generation in 0..config::MCC.find_seed_generation_limit {
+        population.evolve();
+
+        let result = population.run_simulation_and_update_fitness(maze_phenotype, step_budget);
+        if result.is_some() {
+            search.generations = generation + 1;
+            search.solved = true;
+            search.speciation_history = population.speciation_history;
+            record_seed_search(search);
+            return result;
+        }
+    }
+
+    search.speciation_history = population.speciation_history;
+    record_seed_search(search);
+    None
 }
 
 // generate seeds for mcc with neatns.
 // outputs a set of agents and a set of mazes that fulfill the mc.
+// agents are searched with the given sensor layout.
 #[allow(unreachable_code)]
-pub fn generate_seeds(maze_amount: u32, find_double_agents: bool) -> Seeds {
+pub fn generate_seeds(
+    maze_amount: u32,
+    find_double_agents: bool,
+    morphology: &Morphology,
+) -> Seeds {
     let mut mazes_fulfilling_mc: Vec<MazeGenome> = vec![];
     let mut agents_fulfilling_mc: Vec<Agent> = vec![];
 
     let mut threads = vec![];
 
     for i in 0..maze_amount {
+        let morphology = morphology.clone();
         threads.push(thread::spawn(move || {
             loop {
-                let mut generations = 0;
-
                 let mut maze = generate_random_maze(
                     config::MCC.default_maze_size as u32,
                     config::MCC.default_maze_size as u32,
                     i as u32,
                 );
-                let maze_phenotype = maze.to_phenotype();
-
-                let mut population = Population::new(config::NEAT.population_size, 10, 2);
-
-                while generations < config::MCC.find_seed_generation_limit {
-                    population.evolve();
-                    let result = population.run_simulation_and_update_fitness(
-                        &maze_phenotype,
-                        maze.get_solution_path_cell_length(),
-                    );
-
-                    if result.is_some() {
-                        let successful_agent = result.unwrap();
-                        maze.successful_agent_id = Some(successful_agent.id);
 
-                        println!("Found agent maze pair!");
+                if let Some(successful_agent) = search_for_agent(&maze, &morphology) {
+                    maze.successful_agent_id = Some(successful_agent.id);
 
-                        return Some((maze, successful_agent));
-                    }
+                    println!("Found agent maze pair!");
 
-                    generations += 1;
+                    return Some((maze, successful_agent));
                 }
                 println!("Did not find any within generation limit! Generating new maze.");
             }
@@ -81,34 +141,20 @@ pub fn generate_seeds(maze_amount: u32, find_double_agents: bool) -> Seeds {
     }
 
     if !find_double_agents {
-        return Seeds::new(mazes_fulfilling_mc, agents_fulfilling_mc);
+        let mut seeds = Seeds::new(mazes_fulfilling_mc, agents_fulfilling_mc);
+        seeds.assign_individual_ids();
+        return seeds;
     }
 
     let mut agent_threads = vec![];
 
     for maze in mazes_fulfilling_mc.clone() {
+        let morphology = morphology.clone();
         agent_threads.push(thread::spawn(move || -> Option<Agent> {
             loop {
-                let mut generations = 0;
-
-                let maze_phenotype = maze.to_phenotype();
-
-                let mut population = Population::new(config::NEAT.population_size, 10, 2);
-
-                while generations < config::MCC.find_seed_generation_limit {
-                    population.evolve();
-                    let result = population.run_simulation_and_update_fitness(
-                        &maze_phenotype,
-                        maze.get_solution_path_cell_length(),
-                    );
-
-                    if result.is_some() {
-                        let successful_agent = result.unwrap();
-                        println!("Found agent!",);
-                        return Some(successful_agent);
-                    }
-
-                    generations += 1;
+                if let Some(successful_agent) = search_for_agent(&maze, &morphology) {
+                    println!("Found agent!",);
+                    return Some(successful_agent);
                 }
                 println!("Did not find any within generation limit! Resetting.");
             }
@@ -125,31 +171,17 @@ pub fn generate_seeds(maze_amount: u32, find_double_agents: bool) -> Seeds {
             agents_fulfilling_mc.push(agent.clone());
         }
     }
-    Seeds::new(mazes_fulfilling_mc, agents_fulfilling_mc)
+    let mut seeds = Seeds::new(mazes_fulfilling_mc, agents_fulfilling_mc);
+    seeds.assign_individual_ids();
+    seeds
 }
 
-pub fn find_agent_seed_for_maze(maze: MazeGenome) -> Agent {
+pub fn find_agent_seed_for_maze(maze: MazeGenome, morphology: &Morphology) -> Agent {
     loop {
-        let mut generations = 0;
-
-        let maze_phenotype = maze.to_phenotype();
-
-        let mut population = Population::new(config::NEAT.population_size, 10, 2);
-
-        while generations < config::MCC.find_seed_generation_limit {
-            population.evolve();
-            let result = population.run_simulation_and_update_fitness(
-                &maze_phenotype,
-                maze.get_solution_path_cell_length(),
-            );
-
-            if result.is_some() {
-                let successful_agent = result.unwrap();
-                println!("Found agent!",);
-                return successful_agent;
-            }
-
-            generations += 1;
+        if let Some(mut successful_agent) = search_for_agent(&maze, morphology) {
+            successful_agent.id = next_individual_id();
+            println!("Found agent!",);
+            return successful_agent;
         }
         println!("Did not find any within generation limit! Resetting.");
     }
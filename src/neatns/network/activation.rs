@@ -1,6 +1,9 @@
 use rand::Rng;
 use std::str;
 
+// Largest value of the otherwise unbounded activations
+const ACTIVATION_LIMIT: f64 = 10000.0;
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Activation {
     None,
@@ -11,6 +14,19 @@ pub enum Activation {
     Sine,
     Square,
     Exp,
+    Tanh,
+    Step,
+    Abs,
+    Gaussian,      // Width is a parameter of the node
+    ClampedLinear, // Linear within -1.0 and 1.0
+}
+
+/// Squashing of network outputs before they reach the simulator
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum OutputSquashing {
+    None,
+    Tanh,  // Scaled to the output limit
+    Clamp, // Clamped to plus/minus the output limit
 }
 
 #[derive(Clone)]
@@ -19,7 +35,8 @@ pub struct Activations {
 }
 
 impl Activation {
-    pub fn activate(&self, x: f64) -> f64 {
+    /// 'width' is only used by Gaussian
+    pub fn activate(&self, x: f64, width: f64) -> f64 {
         match self {
             Activation::None => x,
             Activation::ReLU => {
@@ -34,15 +51,44 @@ impl Activation {
                 let v = x.exp();
 
                 if v.is_infinite() {
-                    10000.0
+                    ACTIVATION_LIMIT
                 } else {
                     v
                 }
             }
             Activation::Normal => 0.3989422804 * (-0.5 * x.powi(2)).exp(),
             Activation::Sine => x.sin(),
-            Activation::Square => x * x,
-            Activation::Exp => x.exp(),
+            Activation::Square => (x * x).min(ACTIVATION_LIMIT),
+            Activation::Exp => x.exp().min(ACTIVATION_LIMIT),
+            Activation::Tanh => x.tanh(),
+            Activation::Step => {
+                if x > 0.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Activation::Abs => x.abs(),
+            Activation::Gaussian => (-(x / width).powi(2)).exp(),
+            Activation::ClampedLinear => x.clamp(-1.0, 1.0),
+        }
+    }
+}
+
+impl OutputSquashing {
+    pub fn apply(&self, outputs: &mut [f64], limit: f64) {
+        match self {
+            OutputSquashing::None => {}
+            OutputSquashing::Tanh => {
+                for output in outputs.iter_mut() {
+                    *output = limit * output.tanh();
+                }
+            }
+            OutputSquashing::Clamp => {
+                for output in outputs.iter_mut() {
+                    *output = output.clamp(-limit, limit);
+                }
+            }
         }
     }
 }
@@ -76,6 +122,24 @@ impl str::FromStr for Activation {
             "Sine" => Ok(Activation::Sine),
             "Square" => Ok(Activation::Square),
             "Exp" => Ok(Activation::Exp),
+            "Tanh" => Ok(Activation::Tanh),
+            "Step" => Ok(Activation::Step),
+            "Abs" => Ok(Activation::Abs),
+            "Gaussian" => Ok(Activation::Gaussian),
+            "ClampedLinear" => Ok(Activation::ClampedLinear),
+            _ => Err(1),
+        }
+    }
+}
+
+impl str::FromStr for OutputSquashing {
+    type Err = u64;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "None" => Ok(OutputSquashing::None),
+            "Tanh" => Ok(OutputSquashing::Tanh),
+            "Clamp" => Ok(OutputSquashing::Clamp),
             _ => Err(1),
         }
     }
@@ -94,3 +158,52 @@ impl str::FromStr for Activations {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unbounded_activations_are_capped() {
+        assert_eq!(Activation::Square.activate(3.0, 1.0), 9.0);
+        assert_eq!(Activation::Square.activate(-1e6, 1.0), ACTIVATION_LIMIT);
+        assert_eq!(Activation::Exp.activate(0.0, 1.0), 1.0);
+        assert_eq!(Activation::Exp.activate(1e3, 1.0), ACTIVATION_LIMIT);
+    }
+
+    #[test]
+    fn added_activations() {
+        assert_eq!(Activation::Tanh.activate(0.0, 1.0), 0.0);
+        assert!(Activation::Tanh.activate(100.0, 1.0) <= 1.0);
+        assert_eq!(Activation::Step.activate(0.0, 1.0), 0.0);
+        assert_eq!(Activation::Step.activate(0.1, 1.0), 1.0);
+        assert_eq!(Activation::Abs.activate(-2.5, 1.0), 2.5);
+        assert_eq!(Activation::ClampedLinear.activate(0.5, 1.0), 0.5);
+        assert_eq!(Activation::ClampedLinear.activate(-3.0, 1.0), -1.0);
+        assert_eq!(Activation::ClampedLinear.activate(3.0, 1.0), 1.0);
+    }
+
+    #[test]
+    fn gaussian_width() {
+        assert_eq!(Activation::Gaussian.activate(0.0, 0.5), 1.0);
+        assert_eq!(
+            Activation::Gaussian.activate(1.0, 2.0),
+            Activation::Gaussian.activate(0.5, 1.0)
+        );
+        assert!(Activation::Gaussian.activate(1.0, 2.0) > Activation::Gaussian.activate(1.0, 1.0));
+    }
+
+    #[test]
+    fn output_squashing() {
+        let mut outputs = [3.0, -0.5];
+        OutputSquashing::None.apply(&mut outputs, 1.0);
+        assert_eq!(outputs, [3.0, -0.5]);
+
+        OutputSquashing::Clamp.apply(&mut outputs, 2.0);
+        assert_eq!(outputs, [2.0, -0.5]);
+
+        let mut outputs = [100.0, 0.0, -100.0];
+        OutputSquashing::Tanh.apply(&mut outputs, 0.5);
+        assert_eq!(outputs, [0.5, 0.0, -0.5]);
+    }
+}
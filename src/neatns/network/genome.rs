@@ -4,12 +4,14 @@ use crate::neatns::network::connection;
 use crate::neatns::network::innovation::InnovationLog;
 use crate::neatns::network::innovation::InnovationTime;
 use crate::neatns::network::link::Link;
+use crate::neatns::network::mutation::MutationProfile;
 use crate::neatns::network::node::Node;
 use crate::neatns::network::node::NodeRef;
 use crate::neatns::network::order;
 use rand::seq::SliceRandom;
 use rand::Rng;
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Clone)]
 pub struct Genome {
@@ -75,14 +77,15 @@ impl Genome {
 
         let new_node_ref = NodeRef::Hidden(new_node_id);
 
+        // Disable connection
+        self.connections.disable(link.from, link.to);
+
         // Might have inherited that the connection is not split, but also the nodes splitting it
         if self.hidden_nodes.contains_key(&new_node_ref) {
+            self.order.remove_link(link.from, link.to);
             return;
         }
 
-        // Disable connection
-        self.connections.disable(link.from, link.to);
-
         // Add and remvoe actions
         self.order.split_link(link.from, link.to, new_node_ref);
 
@@ -103,6 +106,12 @@ impl Genome {
         // Add link
         self.links.insert((link.from, link.to), link);
 
+        // Recurrent links read the previous activation, so they are not part of the
+        // feed-forward connections and order
+        if link.recurrent {
+            return;
+        }
+
         // Add connections
         self.connections.add(link.from, link.to, link.enabled);
 
@@ -128,10 +137,22 @@ impl Genome {
 
         let mut genome = Genome::empty();
 
+        // Links are aligned by innovation number. Genomes seeded from separate populations
+        // can reuse a number for different links, so the endpoints must match as well.
+        let parent2_links: HashMap<u64, &Link> = parent2
+            .links
+            .values()
+            .map(|link| (link.innovation, link))
+            .collect();
+
         // Copy links only in fitter parent, perform crossover if in both parents
-        for (link_ref, link) in parent1.links.iter() {
-            if !genome.connections.creates_cycle(link.from, link.to) {
-                if let Some(link2) = parent2.links.get(link_ref) {
+        for link in parent1.links.values() {
+            if link.recurrent || !genome.connections.creates_cycle(link.from, link.to) {
+                if let Some(link2) = parent2_links.get(&link.innovation).filter(|link2| {
+                    link2.from == link.from
+                        && link2.to == link.to
+                        && link2.recurrent == link.recurrent
+                }) {
                     genome.insert_link(link.crossover(link2), false);
                 } else {
                     genome.insert_link(*link, false);
@@ -173,55 +194,98 @@ impl Genome {
         return genome;
     }
 
-    pub fn mutate(&mut self, log: &mut InnovationLog, global_innovation: &mut InnovationTime) {
+    pub fn mutate(
+        &mut self,
+        profile: &MutationProfile,
+        log: &mut InnovationLog,
+        global_innovation: &mut InnovationTime,
+    ) {
         let mut rng = rand::thread_rng();
 
-        if rng.gen::<f64>() < config::NEAT.add_node_probability {
+        if rng.gen::<f64>() < profile.add_node {
             self.mutation_add_node(log, global_innovation);
         }
 
-        if rng.gen::<f64>() < config::NEAT.add_connection_probability {
+        if rng.gen::<f64>() < profile.add_connection {
             self.mutation_add_connection(log, global_innovation);
         }
 
-        if rng.gen::<f64>() < config::NEAT.disable_connection_probability {
+        if rng.gen::<f64>() < profile.add_recurrent_connection {
+            self.mutation_add_recurrent_connection(log, global_innovation);
+        }
+
+        if rng.gen::<f64>() < profile.disable_connection {
             self.mutation_disable_connection();
         }
 
-        if rng.gen::<f64>() < config::NEAT.mutate_link_weight_probability {
-            self.mutate_link_weight();
+        if rng.gen::<f64>() < profile.enable_connection {
+            self.mutation_enable_connection();
+        }
+
+        if rng.gen::<f64>() < profile.mutate_link_weight {
+            self.mutate_link_weight(profile.mutate_all_link_weights);
+        }
+
+        if rng.gen::<f64>() < profile.mutate_plasticity {
+            self.mutate_link_plasticity();
         }
 
-        if rng.gen::<f64>() < config::NEAT.mutate_hidden_bias_probability {
+        if rng.gen::<f64>() < profile.mutate_node_parameters {
+            self.mutate_node_parameters();
+        }
+
+        if rng.gen::<f64>() < profile.mutate_hidden_bias {
             self.mutate_hidden_bias();
         }
 
-        if rng.gen::<f64>() < config::NEAT.mutate_hidden_activation_probability {
+        if rng.gen::<f64>() < profile.mutate_hidden_activation {
             self.mutate_hidden_activation();
         }
 
-        if rng.gen::<f64>() < config::NEAT.mutate_output_bias_probability {
+        if rng.gen::<f64>() < profile.mutate_output_bias {
             self.mutate_output_bias();
         }
 
-        if rng.gen::<f64>() < config::NEAT.mutate_output_activation_probability {
+        if rng.gen::<f64>() < profile.mutate_output_activation {
             self.mutate_output_activation();
         }
     }
 
-    fn mutate_link_weight(&mut self) {
+    fn mutate_link_weight(&mut self, all_links: bool) {
         let mut rng = rand::thread_rng();
 
-        // Mutate single link
-        /*if !self.links.is_empty() {
+        if all_links {
+            for link in self.links.values_mut() {
+                link.weight +=
+                    (rng.gen::<f64>() - 0.5) * 2.0 * config::NEAT.mutate_link_weight_size;
+            }
+        } else if !self.links.is_empty() {
+            // Mutate single link
             let link_index = rng.gen_range(0, self.links.len());
-            if let Some(link) = self.links.values_mut().skip(link_index).next() {
-                link.weight += (rng.gen::<f64>() - 0.5) * 2.0 * config::NEAT.mutate_link_weight_size;
+            if let Some(link) = self.links.values_mut().nth(link_index) {
+                link.weight +=
+                    (rng.gen::<f64>() - 0.5) * 2.0 * config::NEAT.mutate_link_weight_size;
             }
-        }*/
+        }
+    }
 
-        for link in self.links.values_mut() {
-            link.weight += (rng.gen::<f64>() - 0.5) * 2.0 * config::NEAT.mutate_link_weight_size;
+    /// Moves the weight of every enabled link uniformly within 'size'
+    pub fn perturb_link_weights(&mut self, size: f64) {
+        let mut rng = rand::thread_rng();
+
+        for link in self.links.values_mut().filter(|link| link.enabled) {
+            link.weight += (rng.gen::<f64>() - 0.5) * 2.0 * size;
+        }
+    }
+
+    fn mutate_link_plasticity(&mut self) {
+        let mut rng = rand::thread_rng();
+
+        if !self.links.is_empty() {
+            let link_index = rng.gen_range(0, self.links.len());
+            if let Some(link) = self.links.values_mut().nth(link_index) {
+                link.plasticity.mutate(config::NEAT.mutate_plasticity_size);
+            }
         }
     }
 
@@ -247,6 +311,27 @@ impl Genome {
         }
     }
 
+    // Perturbs gain and Gaussian width of a random hidden or output node
+    fn mutate_node_parameters(&mut self) {
+        let mut rng = rand::thread_rng();
+        let size = config::NEAT.mutate_node_parameters_size;
+        let node_amount = self.hidden_nodes.len() + self.outputs.len();
+
+        if node_amount > 0 {
+            let node_index = rng.gen_range(0, node_amount);
+            if let Some(node) = self
+                .hidden_nodes
+                .values_mut()
+                .chain(self.outputs.values_mut())
+                .nth(node_index)
+            {
+                node.gain += (rng.gen::<f64>() - 0.5) * 2.0 * size;
+                node.width = (node.width + (rng.gen::<f64>() - 0.5) * 2.0 * size)
+                    .max(config::NEAT.gaussian_width_min);
+            }
+        }
+    }
+
     fn mutate_output_bias(&mut self) {
         let mut rng = rand::thread_rng();
 
@@ -278,7 +363,7 @@ impl Genome {
         if let Some(index) = self
             .links
             .iter()
-            .filter(|(_, link)| !link.split && link.enabled)
+            .filter(|(_, link)| !link.split && link.enabled && !link.recurrent)
             .map(|(i, _)| *i)
             .collect::<Vec<(NodeRef, NodeRef)>>()
             .choose(&mut rand::thread_rng())
@@ -381,6 +466,65 @@ impl Genome {
         }
     }
 
+    // Any node may feed its previous value into a hidden or output node, including itself
+    fn mutation_add_recurrent_connection(
+        &mut self,
+        log: &mut InnovationLog,
+        global_innovation: &mut InnovationTime,
+    ) {
+        let mut rng = rand::thread_rng();
+
+        let from_nodes: Vec<NodeRef> = self
+            .inputs
+            .keys()
+            .chain(self.hidden_nodes.keys())
+            .chain(self.outputs.keys())
+            .cloned()
+            .collect();
+        let to_nodes: Vec<NodeRef> = self
+            .hidden_nodes
+            .keys()
+            .chain(self.outputs.keys())
+            .cloned()
+            .collect();
+
+        // Retry 50 times
+        for _ in 0..50 {
+            if let (Some(&from), Some(&to)) =
+                (from_nodes.choose(&mut rng), to_nodes.choose(&mut rng))
+            {
+                if self.links.contains_key(&(from, to)) {
+                    continue;
+                }
+
+                // Check if this link has been added by another individual
+                let innovation = match log.recurrent_edge_additions.get(&(from, to)) {
+                    Some(innovation_number) => *innovation_number,
+                    None => {
+                        log.recurrent_edge_additions
+                            .insert((from, to), global_innovation.innovation_number);
+                        global_innovation.innovation_number += 1;
+
+                        global_innovation.innovation_number - 1
+                    }
+                };
+
+                let mut link = Link::new(
+                    from,
+                    to,
+                    (rng.gen::<f64>() - 0.5) * 2.0 * config::NEAT.initial_link_weight_size,
+                    innovation,
+                );
+                link.recurrent = true;
+
+                self.insert_link(link, false);
+                break;
+            } else {
+                break;
+            }
+        }
+    }
+
     fn mutation_disable_connection(&mut self) {
         if let Some(&connection_ref) = self
             .links
@@ -392,12 +536,42 @@ impl Genome {
         {
             let connection_ref = *connection_ref;
 
+            if let Some(link) = self.links.get_mut(&connection_ref) {
+                link.enabled = false;
+
+                if link.recurrent {
+                    return;
+                }
+            }
+
             self.connections.disable(connection_ref.0, connection_ref.1);
             self.order.remove_link(connection_ref.0, connection_ref.1);
+        }
+    }
+
+    // Links disabled by a split are left alone, as the split node replaces them
+    fn mutation_enable_connection(&mut self) {
+        if let Some(&connection_ref) = self
+            .links
+            .iter()
+            .filter(|(_, link)| !link.enabled && !link.split)
+            .map(|(i, _)| i)
+            .collect::<Vec<&(NodeRef, NodeRef)>>()
+            .choose(&mut rand::thread_rng())
+        {
+            let connection_ref = *connection_ref;
 
             if let Some(link) = self.links.get_mut(&connection_ref) {
-                link.enabled = false;
+                link.enabled = true;
+
+                if link.recurrent {
+                    return;
+                }
             }
+
+            self.connections.enable(connection_ref.0, connection_ref.1);
+            self.order
+                .add_link(connection_ref.0, connection_ref.1, &self.connections);
         }
     }
 
@@ -416,7 +590,11 @@ impl Genome {
 
         for (link_ref, link) in self.links.iter() {
             if let Some(link2) = other.links.get(link_ref) {
-                link_distance += link.distance(link2); // Distance normalized between 0 and 1
+                if link.recurrent != link2.recurrent {
+                    link_differences += 1; // Same nodes, but a different kind of link
+                } else {
+                    link_distance += link.distance(link2); // Distance normalized between 0 and 1
+                }
             } else {
                 link_differences += 1;
             }
@@ -429,6 +607,142 @@ impl Genome {
         };
     }
 
+    /// Nodes with a path of enabled links to an output, including the outputs.
+    /// Recurrent links count, as they carry values to later activations.
+    fn functional_nodes(&self) -> HashSet<NodeRef> {
+        let mut sources: HashMap<NodeRef, Vec<NodeRef>> = HashMap::new();
+        for link in self.links.values().filter(|link| link.enabled) {
+            sources.entry(link.to).or_default().push(link.from);
+        }
+
+        let mut functional: HashSet<NodeRef> = self.outputs.keys().cloned().collect();
+        let mut queue: Vec<NodeRef> = self.outputs.keys().cloned().collect();
+
+        while let Some(node) = queue.pop() {
+            for source in sources.get(&node).into_iter().flatten() {
+                if functional.insert(*source) {
+                    queue.push(*source);
+                }
+            }
+        }
+
+        functional
+    }
+
+    /// Number of enabled links that can affect an output
+    pub fn functional_size(&self) -> usize {
+        let functional = self.functional_nodes();
+
+        self.links
+            .values()
+            .filter(|link| link.enabled && functional.contains(&link.to))
+            .count()
+    }
+
+    /// Removes disabled links, and hidden nodes that can not reach an output along with their links.
+    /// The network computes the same outputs afterwards.
+    pub fn simplify(&mut self) {
+        let disabled: Vec<Link> = self
+            .links
+            .values()
+            .filter(|link| !link.enabled)
+            .cloned()
+            .collect();
+
+        for link in disabled.iter() {
+            self.links.remove(&(link.from, link.to));
+
+            if !link.recurrent {
+                self.connections.remove_disabled(&link.from, link.to);
+            }
+        }
+
+        let functional = self.functional_nodes();
+        let dead_nodes: Vec<NodeRef> = self
+            .hidden_nodes
+            .keys()
+            .filter(|node| !functional.contains(node))
+            .cloned()
+            .collect();
+
+        if dead_nodes.is_empty() {
+            return;
+        }
+
+        let dead_links: Vec<Link> = self
+            .links
+            .values()
+            .filter(|link| dead_nodes.contains(&link.from) || dead_nodes.contains(&link.to))
+            .cloned()
+            .collect();
+
+        for link in dead_links.iter() {
+            self.links.remove(&(link.from, link.to));
+
+            if !link.recurrent {
+                self.connections.remove_enabled(&link.from, link.to);
+                self.order.remove_link(link.from, link.to);
+            }
+        }
+
+        for node in dead_nodes.iter() {
+            self.hidden_nodes.remove(node);
+            self.order.remove_hidden(*node);
+        }
+    }
+
+    /// Adds an unconnected input node reading network input 'id'
+    pub fn add_input(&mut self, id: u64) {
+        let node_ref = NodeRef::Input(id);
+
+        if let Entry::Vacant(entry) = self.inputs.entry(node_ref) {
+            entry.insert(Node::new(node_ref));
+            self.order.add_input(node_ref);
+        }
+    }
+
+    /// Removes the input node reading network input 'id' along with its links
+    pub fn remove_input(&mut self, id: u64) {
+        let node_ref = NodeRef::Input(id);
+
+        let links: Vec<Link> = self
+            .links
+            .values()
+            .filter(|link| link.from == node_ref)
+            .cloned()
+            .collect();
+
+        for link in links.iter() {
+            self.links.remove(&(link.from, link.to));
+
+            if !link.recurrent {
+                self.connections.remove(&link.from, link.to, link.enabled);
+                self.order.remove_link(link.from, link.to);
+            }
+        }
+
+        self.inputs.remove(&node_ref);
+        self.order.remove_input(node_ref);
+    }
+
+    /// Node and innovation numbers following the highest ones used in this genome
+    pub fn next_innovation_time(&self) -> InnovationTime {
+        InnovationTime {
+            node_number: self
+                .hidden_nodes
+                .keys()
+                .map(|node_ref| node_ref.id() + 1)
+                .max()
+                .unwrap_or(0),
+            innovation_number: self
+                .links
+                .values()
+                .map(|link| link.innovation + 1)
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
     pub fn get_activation(&self, node_ref: &NodeRef) -> activation::Activation {
         match node_ref {
             NodeRef::Input(_) => self.inputs.get(node_ref).unwrap().activation,
@@ -437,6 +751,22 @@ impl Genome {
         }
     }
 
+    pub fn get_gain(&self, node_ref: &NodeRef) -> f64 {
+        match node_ref {
+            NodeRef::Input(_) => self.inputs.get(node_ref).unwrap().gain,
+            NodeRef::Hidden(_) => self.hidden_nodes.get(node_ref).unwrap().gain,
+            NodeRef::Output(_) => self.outputs.get(node_ref).unwrap().gain,
+        }
+    }
+
+    pub fn get_width(&self, node_ref: &NodeRef) -> f64 {
+        match node_ref {
+            NodeRef::Input(_) => self.inputs.get(node_ref).unwrap().width,
+            NodeRef::Hidden(_) => self.hidden_nodes.get(node_ref).unwrap().width,
+            NodeRef::Output(_) => self.outputs.get(node_ref).unwrap().width,
+        }
+    }
+
     pub fn get_bias(&self, node_ref: &NodeRef) -> f64 {
         match node_ref {
             NodeRef::Input(_) => self.inputs.get(node_ref).unwrap().bias,
@@ -445,3 +775,106 @@ impl Genome {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neatns::network::neural_network::NeuralNetwork;
+
+    #[test]
+    fn simplify_keeps_outputs_and_removes_dead_topology() {
+        let profile = MutationProfile {
+            add_node: 0.3,
+            add_connection: 0.6,
+            add_recurrent_connection: 0.1,
+            disable_connection: 0.3,
+            enable_connection: 0.0,
+            mutate_link_weight: 0.8,
+            mutate_all_link_weights: false,
+            mutate_plasticity: 0.0,
+            mutate_node_parameters: 0.0,
+            mutate_hidden_bias: 0.5,
+            mutate_hidden_activation: 0.0,
+            mutate_output_bias: 0.5,
+            mutate_output_activation: 0.0,
+        };
+        let mut log = InnovationLog::new();
+        let mut global_innovation = InnovationTime::new();
+        let inputs = [0.3, -0.7, 0.9, 0.1];
+
+        for _ in 0..20 {
+            let mut genome = Genome::new(4, 2);
+            for _ in 0..60 {
+                genome.mutate(&profile, &mut log, &mut global_innovation);
+            }
+
+            let mut simplified = genome.clone();
+            simplified.simplify();
+
+            assert!(simplified.links.values().all(|link| link.enabled));
+            assert_eq!(simplified.functional_size(), genome.functional_size());
+            assert_eq!(simplified.links.len(), simplified.functional_size());
+
+            let mut network = NeuralNetwork::new(&genome);
+            let mut simplified_network = NeuralNetwork::new(&simplified);
+            for _ in 0..3 {
+                assert_eq!(
+                    network.activate(&inputs).to_vec(),
+                    simplified_network.activate(&inputs).to_vec()
+                );
+            }
+        }
+    }
+
+    /// Child of a parent that split the link from input 0 to output 0 and a parent that did
+    /// not, where the child splits the inherited link again with the node it already has
+    fn resplit_inherited_link() -> Genome {
+        let link = Link::new(NodeRef::Input(0), NodeRef::Output(0), 0.5, 0);
+        let mut unsplit = Genome::new(2, 1);
+        unsplit.insert_link(link, true);
+
+        let mut split = unsplit.clone();
+        split.split_link(link, 0, 1);
+
+        let mut child = split.crossover(&unsplit, true);
+        assert!(child.links[&(link.from, link.to)].enabled);
+        child.split_link(link, 0, 1);
+
+        assert!(!child.connections.contains_enabled(&link.from, link.to));
+        assert!(!child
+            .order
+            .contains(&order::Action::Link(link.from, link.to)));
+
+        child
+    }
+
+    #[test]
+    fn simplify_after_resplitting_inherited_link() {
+        let genome = resplit_inherited_link();
+        let inputs = [0.3, -0.7];
+
+        let mut simplified = genome.clone();
+        simplified.simplify();
+
+        assert_eq!(simplified.links.len(), 2);
+        assert_eq!(
+            NeuralNetwork::new(&genome).activate(&inputs).to_vec(),
+            NeuralNetwork::new(&simplified).activate(&inputs).to_vec()
+        );
+    }
+
+    #[test]
+    fn remove_input_with_split_link() {
+        let input = NodeRef::Input(0);
+        let mut genome = resplit_inherited_link();
+        genome.remove_input(0);
+
+        assert!(!genome.inputs.contains_key(&input));
+        assert!(genome.links.keys().all(|(from, _)| *from != input));
+        assert!(!genome.connections.contains(&input, NodeRef::Output(0)));
+        assert!(!genome.order.contains(&order::Action::Activation(input)));
+        assert_eq!(genome.links.len(), 1);
+
+        NeuralNetwork::new(&genome).activate(&[0.3, -0.7]);
+    }
+}
@@ -4,6 +4,7 @@ use std::collections::HashMap;
 pub struct InnovationLog {
     pub node_additions: HashMap<u64, InnovationTime>,
     pub edge_additions: HashMap<(node::NodeRef, node::NodeRef), u64>,
+    pub recurrent_edge_additions: HashMap<(node::NodeRef, node::NodeRef), u64>,
 }
 
 pub struct InnovationTime {
@@ -16,6 +17,7 @@ impl InnovationLog {
         InnovationLog {
             node_additions: HashMap::new(),
             edge_additions: HashMap::new(),
+            recurrent_edge_additions: HashMap::new(),
         }
     }
 }
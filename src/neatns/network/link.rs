@@ -1,4 +1,5 @@
 use crate::neatns::network::node::NodeRef;
+use crate::neatns::network::plasticity::HebbianRule;
 
 /// Link between two nodes
 #[derive(Copy, Clone, Debug)]
@@ -9,6 +10,8 @@ pub struct Link {
     pub enabled: bool,
     pub split: bool,     // Link has been split
     pub innovation: u64, // Global innovation number
+    pub recurrent: bool, // Link carries the value of the previous activation
+    pub plasticity: HebbianRule,
 }
 
 pub trait Custom: Copy + Clone + Send {
@@ -25,6 +28,8 @@ impl Link {
             enabled: true,
             split: false,
             innovation,
+            recurrent: false,
+            plasticity: HebbianRule::none(),
         }
     }
 
@@ -32,6 +37,7 @@ impl Link {
         assert_eq!(self.from, other.from);
         assert_eq!(self.to, other.to);
         assert_eq!(self.innovation, other.innovation);
+        assert_eq!(self.recurrent, other.recurrent);
 
         Link {
             from: self.from,
@@ -40,6 +46,8 @@ impl Link {
             enabled: self.enabled || other.enabled,
             split: self.split && other.split,
             innovation: self.innovation,
+            recurrent: self.recurrent,
+            plasticity: self.plasticity.crossover(&other.plasticity),
         }
     }
 
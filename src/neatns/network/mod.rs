@@ -4,6 +4,9 @@ pub mod connection;
 pub mod genome;
 pub mod innovation;
 pub mod link;
+pub mod mutation;
 pub mod neural_network;
 pub mod node;
 pub mod order;
+pub mod plasticity;
+pub mod substrate;
@@ -0,0 +1,57 @@
+use crate::config;
+
+/// Probabilities of each mutation, so NEAT-NS seeding and MCC can share one genome type
+#[derive(Debug, Clone, Copy)]
+pub struct MutationProfile {
+    pub add_node: f64,
+    pub add_connection: f64,
+    pub add_recurrent_connection: f64,
+    pub disable_connection: f64,
+    pub enable_connection: f64,
+    pub mutate_link_weight: f64,
+    pub mutate_all_link_weights: bool, // Mutate every link instead of a single random link
+    pub mutate_plasticity: f64,
+    pub mutate_node_parameters: f64, // Gain and Gaussian width
+    pub mutate_hidden_bias: f64,
+    pub mutate_hidden_activation: f64,
+    pub mutate_output_bias: f64,
+    pub mutate_output_activation: f64,
+}
+
+impl MutationProfile {
+    pub fn neatns() -> MutationProfile {
+        MutationProfile {
+            add_node: config::NEAT.add_node_probability,
+            add_connection: config::NEAT.add_connection_probability,
+            add_recurrent_connection: config::NEAT.add_recurrent_connection_probability,
+            disable_connection: config::NEAT.disable_connection_probability,
+            enable_connection: 0.0,
+            mutate_link_weight: config::NEAT.mutate_link_weight_probability,
+            mutate_all_link_weights: true,
+            mutate_plasticity: config::NEAT.mutate_plasticity_probability,
+            mutate_node_parameters: config::NEAT.mutate_node_parameters_probability,
+            mutate_hidden_bias: config::NEAT.mutate_hidden_bias_probability,
+            mutate_hidden_activation: config::NEAT.mutate_hidden_activation_probability,
+            mutate_output_bias: config::NEAT.mutate_output_bias_probability,
+            mutate_output_activation: config::NEAT.mutate_output_activation_probability,
+        }
+    }
+
+    pub fn mcc() -> MutationProfile {
+        MutationProfile {
+            add_node: config::AGENT.add_neuron,
+            add_connection: config::AGENT.add_connection,
+            add_recurrent_connection: config::AGENT.add_recurrent_connection,
+            disable_connection: config::AGENT.disable_connection,
+            enable_connection: config::AGENT.enable_connection,
+            mutate_link_weight: config::AGENT.mutate_weight,
+            mutate_all_link_weights: false,
+            mutate_plasticity: config::AGENT.mutate_plasticity,
+            mutate_node_parameters: config::AGENT.mutate_node_parameters,
+            mutate_hidden_bias: config::AGENT.mutate_hidden_bias,
+            mutate_hidden_activation: config::AGENT.mutate_hidden_activation,
+            mutate_output_bias: config::AGENT.mutate_output_bias,
+            mutate_output_activation: config::AGENT.mutate_output_activation,
+        }
+    }
+}
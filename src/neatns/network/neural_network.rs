@@ -1,22 +1,55 @@
 use std::collections::HashMap;
 
+use crate::config;
 use crate::neatns::network::activation::Activation;
 use crate::neatns::network::genome::Genome;
 use crate::neatns::network::node::NodeRef;
 use crate::neatns::network::order;
+use crate::neatns::network::plasticity::HebbianRule;
 
 #[derive(Clone, Debug)]
-pub enum Action {
-    Link(usize, usize, f64),
-    // from, to, weight
-    Activation(usize, f64, Activation), // node, bias, activation
+struct Link {
+    from: usize,
+    to: usize,
+    weight: f64,
 }
 
+// Link whose weight follows a Hebbian rule during the episode, 'index' points into the links or recurrent links
+#[derive(Clone, Debug)]
+struct PlasticLink {
+    index: usize,
+    rule: HebbianRule,
+    initial_weight: f64,
+}
+
+// Activation of a node, applied once all links before 'links_end' have been summed
+#[derive(Clone, Debug)]
+struct Step {
+    node: usize,
+    bias: f64,
+    gain: f64,
+    width: f64,
+    activation: Activation,
+    links_end: usize,
+}
+
+// Compiled network. Links are stored contiguously in evaluation order,
+// and all buffers are allocated once, so activation does not allocate.
+// Recurrent links read node values from the previous activation, and plastic links
+// change their weights after every activation, until 'reset' is called.
+#[derive(Clone)]
 pub struct NeuralNetwork {
     values: Vec<f64>,
     inputs: Vec<usize>,
     outputs: Vec<usize>,
-    actions: Vec<Action>,
+    output_values: Vec<f64>,
+    links: Vec<Link>,
+    steps: Vec<Step>,
+    recurrent_links: Vec<Link>,
+    previous_values: Vec<f64>,
+    plastic_links: Vec<PlasticLink>,
+    plastic_recurrent_links: Vec<PlasticLink>,
+    weight_limit: f64,
 }
 
 impl NeuralNetwork {
@@ -49,23 +82,73 @@ impl NeuralNetwork {
             )
             .collect();
 
-        let actions = genome
-            .order
+        let mut links: Vec<Link> = vec![];
+        let mut steps: Vec<Step> = vec![];
+        let mut plastic_links: Vec<PlasticLink> = vec![];
+
+        for action in genome.order.iter() {
+            match action {
+                order::Action::Link(from, to) => {
+                    let link = genome.links.get(&(*from, *to)).unwrap();
+
+                    if link.plasticity.is_active() {
+                        plastic_links.push(PlasticLink {
+                            index: links.len(),
+                            rule: link.plasticity,
+                            initial_weight: link.weight,
+                        });
+                    }
+
+                    links.push(Link {
+                        from: *node_mapper.get(from).unwrap(),
+                        to: *node_mapper.get(to).unwrap(),
+                        weight: link.weight,
+                    });
+                }
+                order::Action::Activation(node) => steps.push(Step {
+                    node: *node_mapper.get(node).unwrap(),
+                    bias: genome.get_bias(node),
+                    gain: genome.get_gain(node),
+                    width: genome.get_width(node),
+                    activation: genome.get_activation(node),
+                    links_end: links.len(),
+                }),
+            }
+        }
+
+        let genome_recurrent_links: Vec<_> = genome
+            .links
+            .values()
+            .filter(|link| link.recurrent && link.enabled)
+            .collect();
+
+        let recurrent_links: Vec<Link> = genome_recurrent_links
             .iter()
-            .map(|action| match action {
-                order::Action::Link(from, to) => Action::Link(
-                    *node_mapper.get(from).unwrap(),
-                    *node_mapper.get(to).unwrap(),
-                    genome.links.get(&(*from, *to)).unwrap().weight,
-                ),
-                order::Action::Activation(node) => Action::Activation(
-                    *node_mapper.get(node).unwrap(),
-                    genome.get_bias(node),
-                    genome.get_activation(node),
-                ),
+            .map(|link| Link {
+                from: *node_mapper.get(&link.from).unwrap(),
+                to: *node_mapper.get(&link.to).unwrap(),
+                weight: link.weight,
+            })
+            .collect();
+
+        let plastic_recurrent_links: Vec<PlasticLink> = genome_recurrent_links
+            .iter()
+            .enumerate()
+            .filter(|(_, link)| link.plasticity.is_active())
+            .map(|(index, link)| PlasticLink {
+                index,
+                rule: link.plasticity,
+                initial_weight: link.weight,
             })
             .collect();
 
+        // Previous values are only kept when there is something to read them
+        let previous_length = if recurrent_links.is_empty() {
+            0
+        } else {
+            cumulative_output_length
+        };
+
         NeuralNetwork {
             values: vec![0.0; cumulative_output_length],
             inputs: input_keys.iter().map(|node| node.id() as usize).collect(),
@@ -73,25 +156,91 @@ impl NeuralNetwork {
                 .iter()
                 .map(|node| node.id() as usize + cumulative_hidden_length)
                 .collect(),
-            actions,
+            output_values: vec![0.0; output_keys.len()],
+            links,
+            steps,
+            recurrent_links,
+            previous_values: vec![0.0; previous_length],
+            plastic_links,
+            plastic_recurrent_links,
+            weight_limit: config::NEAT.plastic_weight_limit,
         }
     }
 
-    /// Evaluate network, takes input node values, returns output node values
-    pub fn activate(&mut self, inputs: &Vec<f64>) -> Vec<f64> {
-        for i in 0..self.values.len() {
-            self.values[i] = 0.0;
+    /// Network of fixed layers where nodes are numbered inputs first, then hidden nodes, then outputs.
+    /// Input node 'i' reads network input 'input_ids[i]'.
+    /// Links are (from, to, weight) and may only go from inputs to hidden nodes or outputs,
+    /// or from hidden nodes to outputs.
+    pub fn layered(
+        input_ids: &[usize],
+        hidden_nodes: &[(f64, Activation)],
+        outputs: &[(f64, Activation)],
+        links: &[(usize, usize, f64)],
+    ) -> NeuralNetwork {
+        let input_amount = input_ids.len();
+        let hidden_end = input_amount + hidden_nodes.len();
+
+        // Links into hidden nodes are summed before any hidden node is activated
+        let mut links: Vec<Link> = links
+            .iter()
+            .map(|(from, to, weight)| Link {
+                from: *from,
+                to: *to,
+                weight: *weight,
+            })
+            .collect();
+        links.sort_by_key(|link| link.to >= hidden_end);
+        let hidden_links_end = links.iter().filter(|link| link.to < hidden_end).count();
+
+        let steps: Vec<Step> = hidden_nodes
+            .iter()
+            .enumerate()
+            .map(|(i, (bias, activation))| Step {
+                node: input_amount + i,
+                bias: *bias,
+                gain: 1.0,
+                width: 1.0,
+                activation: *activation,
+                links_end: hidden_links_end,
+            })
+            .chain(
+                outputs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (bias, activation))| Step {
+                        node: hidden_end + i,
+                        bias: *bias,
+                        gain: 1.0,
+                        width: 1.0,
+                        activation: *activation,
+                        links_end: links.len(),
+                    }),
+            )
+            .collect();
+
+        NeuralNetwork {
+            values: vec![0.0; hidden_end + outputs.len()],
+            inputs: input_ids.to_vec(),
+            outputs: (hidden_end..hidden_end + outputs.len()).collect(),
+            output_values: vec![0.0; outputs.len()],
+            links,
+            steps,
+            recurrent_links: vec![],
+            previous_values: vec![],
+            plastic_links: vec![],
+            plastic_recurrent_links: vec![],
+            weight_limit: config::NEAT.plastic_weight_limit,
         }
+    }
 
-        // Clear network
-        // Same as loop above, but this unsafe implementation is faster
-        /*unsafe {
-            libc::memset(
-                self.values.as_mut_ptr() as _,
-                0,
-                self.values.len() * mem::size_of::<f64>(),
-            );
-        }*/
+    /// Evaluate network, takes input node values, returns output node values.
+    /// The returned slice is overwritten by the next activation.
+    pub fn activate(&mut self, inputs: &[f64]) -> &[f64] {
+        self.values.fill(0.0);
+
+        for l in self.recurrent_links.iter() {
+            self.values[l.to] += self.previous_values[l.from] * l.weight;
+        }
 
         // Copy inputs into values
         for (i, index) in self.inputs.iter().enumerate() {
@@ -99,27 +248,207 @@ impl NeuralNetwork {
         }
 
         // Do forward pass
-        for action in self.actions.iter() {
-            match action {
-                Action::Link(from, to, weight) => {
-                    self.values[*to] += self.values[*from] * weight;
-                }
-                Action::Activation(node, bias, activation) => {
-                    self.values[*node] = activation.activate(self.values[*node] + *bias)
-                }
+        let mut link = 0;
+
+        for step in self.steps.iter() {
+            for l in self.links[link..step.links_end].iter() {
+                self.values[l.to] += self.values[l.from] * l.weight;
             }
+            link = step.links_end;
+
+            self.values[step.node] = step
+                .activation
+                .activate(step.gain * (self.values[step.node] + step.bias), step.width);
+        }
+
+        for l in self.links[link..].iter() {
+            self.values[l.to] += self.values[l.from] * l.weight;
+        }
+
+        self.update_plastic_weights();
+
+        if !self.recurrent_links.is_empty() {
+            self.previous_values.copy_from_slice(&self.values);
         }
 
         // Collect output
-        self.outputs
+        for (output, index) in self.output_values.iter_mut().zip(self.outputs.iter()) {
+            let value = self.values[*index];
+            *output = if value.is_finite() { value } else { 0.0 };
+        }
+
+        &self.output_values
+    }
+
+    // Applies the Hebbian rules, using the values of the activation that just finished
+    fn update_plastic_weights(&mut self) {
+        let limit = self.weight_limit;
+
+        for plastic in self.plastic_links.iter() {
+            let link = &mut self.links[plastic.index];
+            let change = plastic
+                .rule
+                .weight_change(self.values[link.from], self.values[link.to]);
+
+            if change.is_finite() {
+                link.weight = (link.weight + change).max(-limit).min(limit);
+            }
+        }
+
+        for plastic in self.plastic_recurrent_links.iter() {
+            let link = &mut self.recurrent_links[plastic.index];
+            let change = plastic
+                .rule
+                .weight_change(self.previous_values[link.from], self.values[link.to]);
+
+            if change.is_finite() {
+                link.weight = (link.weight + change).max(-limit).min(limit);
+            }
+        }
+    }
+
+    /// Clears the state kept by recurrent and plastic links, called at the start of every episode
+    pub fn reset(&mut self) {
+        self.previous_values.fill(0.0);
+
+        for plastic in self.plastic_links.iter() {
+            self.links[plastic.index].weight = plastic.initial_weight;
+        }
+
+        for plastic in self.plastic_recurrent_links.iter() {
+            self.recurrent_links[plastic.index].weight = plastic.initial_weight;
+        }
+    }
+
+    /// True if outputs depend only on the current inputs, without recurrent or plastic links
+    pub fn is_stateless(&self) -> bool {
+        self.recurrent_links.is_empty()
+            && self.plastic_links.is_empty()
+            && self.plastic_recurrent_links.is_empty()
+    }
+
+    pub fn output_amount(&self) -> usize {
+        self.output_values.len()
+    }
+}
+
+/// A set of networks stepped together on shared flat input and output buffers.
+/// Network 'i' reads inputs from 'input(i)' and writes its outputs to 'output(i)'.
+#[derive(Clone)]
+pub struct NetworkBatch {
+    networks: Vec<NeuralNetwork>,
+    input_size: usize,
+    output_size: usize,
+    inputs: Vec<f64>,
+    outputs: Vec<f64>,
+}
+
+impl NetworkBatch {
+    pub fn new(networks: Vec<NeuralNetwork>, input_size: usize) -> NetworkBatch {
+        let output_size = networks
             .iter()
-            .map(|o| {
-                if self.values[*o].is_finite() {
-                    self.values[*o]
-                } else {
-                    0.0
-                }
-            })
-            .collect()
+            .map(|network| network.output_amount())
+            .max()
+            .unwrap_or(0);
+
+        NetworkBatch {
+            inputs: vec![0.0; networks.len() * input_size],
+            outputs: vec![0.0; networks.len() * output_size],
+            networks,
+            input_size,
+            output_size,
+        }
+    }
+
+    pub fn input(&mut self, index: usize) -> &mut [f64] {
+        &mut self.inputs[index * self.input_size..(index + 1) * self.input_size]
+    }
+
+    pub fn output(&mut self, index: usize) -> &mut [f64] {
+        &mut self.outputs[index * self.output_size..(index + 1) * self.output_size]
+    }
+
+    /// Activates every network where 'active' is set, skipped networks keep their last outputs
+    pub fn activate(&mut self, active: &[bool]) {
+        let (input_size, output_size) = (self.input_size, self.output_size);
+
+        for (i, network) in self.networks.iter_mut().enumerate() {
+            if !active[i] {
+                continue;
+            }
+
+            let outputs = network.activate(&self.inputs[i * input_size..(i + 1) * input_size]);
+            self.outputs[i * output_size..i * output_size + outputs.len()].copy_from_slice(outputs);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neatns::network::link;
+
+    const INPUT: NodeRef = NodeRef::Input(0);
+    const OUTPUT: NodeRef = NodeRef::Output(0);
+
+    // One input linked to one output, optionally with a recurrent link from the output to itself
+    fn genome(plasticity: HebbianRule, recurrent: bool) -> Genome {
+        let mut genome = Genome::new(1, 1);
+
+        let output = genome.outputs.get_mut(&OUTPUT).unwrap();
+        output.activation = Activation::Tanh;
+        output.bias = 0.2;
+
+        let mut forward = link::Link::new(INPUT, OUTPUT, 0.5, 0);
+        forward.plasticity = plasticity;
+        genome.links.insert((INPUT, OUTPUT), forward);
+        genome.connections.add_enabled(INPUT, OUTPUT);
+        genome.order.add_link(INPUT, OUTPUT, &genome.connections);
+
+        if recurrent {
+            let mut link = link::Link::new(OUTPUT, OUTPUT, 0.8, 1);
+            link.recurrent = true;
+            link.plasticity = plasticity;
+            genome.links.insert((OUTPUT, OUTPUT), link);
+        }
+
+        genome
+    }
+
+    // Outputs of three activations with the same input
+    fn episode(network: &mut NeuralNetwork) -> Vec<f64> {
+        (0..3).map(|_| network.activate(&[1.0])[0]).collect()
+    }
+
+    #[test]
+    fn reset_clears_recurrent_state() {
+        let mut network = NeuralNetwork::new(&genome(HebbianRule::none(), true));
+
+        let first = episode(&mut network);
+        assert_ne!(first[0], first[1]);
+
+        network.reset();
+        assert_eq!(episode(&mut network), first);
+    }
+
+    #[test]
+    fn reset_restores_plastic_weights() {
+        let rule = HebbianRule {
+            learning_rate: 0.1,
+            a: 1.0,
+            b: 0.0,
+            c: 0.0,
+            d: 0.5,
+        };
+        let mut network = NeuralNetwork::new(&genome(rule, true));
+
+        let first = episode(&mut network);
+        let mut fixed = NeuralNetwork::new(&genome(HebbianRule::none(), true));
+        assert_ne!(first, episode(&mut fixed));
+
+        network.reset();
+        assert_eq!(network.links[0].weight, 0.5);
+        assert_eq!(network.recurrent_links[0].weight, 0.8);
+        assert_eq!(episode(&mut network), first);
     }
 }
@@ -8,6 +8,8 @@ pub struct Node {
     pub node_ref: NodeRef,
     pub bias: f64,
     pub activation: Activation,
+    pub gain: f64,  // Scales the summed input before activation
+    pub width: f64, // Width of Gaussian activations
 }
 
 impl Node {
@@ -15,6 +17,8 @@ impl Node {
         Node {
             node_ref: node_ref,
             bias: 0.0,
+            gain: 1.0,
+            width: 1.0,
             activation: match node_ref {
                 NodeRef::Input(_) => Activation::None,
                 NodeRef::Hidden(_) => config::NEAT.hidden_activations.random(),
@@ -29,6 +33,8 @@ impl Node {
         Node {
             node_ref: self.node_ref,
             bias: (self.bias + other.bias) / 2.0,
+            gain: (self.gain + other.gain) / 2.0,
+            width: (self.width + other.width) / 2.0,
             activation: if rand::thread_rng().gen::<bool>() {
                 self.activation
             } else {
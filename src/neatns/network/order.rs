@@ -51,6 +51,13 @@ impl<T: Hash + Eq + Copy> Order<T> {
         self.actions.insert(0, Action::Activation(node));
     }
 
+    /// Removes an input node activation, its links must be removed first
+    pub fn remove_input(&mut self, node: T) {
+        self.inputs.retain(|input| *input != node);
+        self.actions
+            .retain(|action| *action != Action::Activation(node));
+    }
+
     pub fn add_output(&mut self, node: T) {
         self.outputs.push(node);
         // New outputs are appended at the end
@@ -63,6 +70,13 @@ impl<T: Hash + Eq + Copy> Order<T> {
         self.hiddens.push(node);
     }
 
+    /// Removes a hidden node activation, its links must be removed first
+    pub fn remove_hidden(&mut self, node: T) {
+        self.hiddens.retain(|hidden| *hidden != node);
+        self.actions
+            .retain(|action| *action != Action::Activation(node));
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &Action<T>> {
         self.actions.iter()
     }
@@ -88,6 +102,9 @@ impl<T: Hash + Eq + Copy> Order<T> {
     }
 
     pub fn split_link(&mut self, from: T, to: T, new: T) {
+        // Keep the new node when sorting topologically, even if all its incoming links are disabled
+        self.hiddens.push(new);
+
         let mut skip = 0;
 
         // Insert link between 'from' and 'new' after 'from'-activation
@@ -337,4 +354,22 @@ mod tests {
         assert!(pos(1) < pos(3));
         assert!(pos(2) < pos(3));
     }
+
+    #[test]
+    fn test_split_node_kept_when_sorting() {
+        let mut connections = Connections::<u8>::new();
+        connections.add_disabled(0, 1);
+        connections.add_disabled(0, 2);
+        connections.add_enabled(2, 1);
+
+        let mut order = Order::<u8>::from_nodes(vec![0], vec![], vec![1]);
+        order.add_link(0, 1, &connections);
+        order.split_link(0, 1, 2);
+        order.remove_link(0, 2);
+
+        order.sort_topologically(&connections);
+
+        assert!(order.contains(&Action::Activation(2)));
+        assert!(order.contains(&Action::Link(2, 1)));
+    }
 }
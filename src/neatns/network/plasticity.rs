@@ -0,0 +1,59 @@
+use rand::Rng;
+
+/// Evolvable Hebbian learning rule of a link.
+/// The weight changes by learning_rate * (a * pre * post + b * pre + c * post + d) every activation,
+/// where pre and post are the activated values of the linked nodes.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HebbianRule {
+    pub learning_rate: f64,
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+}
+
+impl HebbianRule {
+    /// Rule that leaves the weight unchanged
+    pub fn none() -> HebbianRule {
+        HebbianRule {
+            learning_rate: 0.0,
+            a: 0.0,
+            b: 0.0,
+            c: 0.0,
+            d: 0.0,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.learning_rate != 0.0
+    }
+
+    pub fn weight_change(&self, pre: f64, post: f64) -> f64 {
+        self.learning_rate * (self.a * pre * post + self.b * pre + self.c * post + self.d)
+    }
+
+    pub fn crossover(&self, other: &Self) -> HebbianRule {
+        HebbianRule {
+            learning_rate: (self.learning_rate + other.learning_rate) / 2.0,
+            a: (self.a + other.a) / 2.0,
+            b: (self.b + other.b) / 2.0,
+            c: (self.c + other.c) / 2.0,
+            d: (self.d + other.d) / 2.0,
+        }
+    }
+
+    /// Perturbs every coefficient uniformly within 'size'
+    pub fn mutate(&mut self, size: f64) {
+        let mut rng = rand::thread_rng();
+
+        for value in [
+            &mut self.learning_rate,
+            &mut self.a,
+            &mut self.b,
+            &mut self.c,
+            &mut self.d,
+        ] {
+            *value += (rng.gen::<f64>() - 0.5) * 2.0 * size;
+        }
+    }
+}
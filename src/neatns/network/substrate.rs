@@ -0,0 +1,193 @@
+use std::str;
+
+use crate::config;
+use crate::neatns::network::genome::Genome;
+use crate::neatns::network::neural_network::NeuralNetwork;
+use crate::simulator::morphology::Morphology;
+use crate::simulator::radar::RADAR_AMOUNT;
+
+/// How an agent genome is turned into a controller
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Encoding {
+    Direct, // The genome is the controller
+    Cppn,   // The genome is queried for the weights and biases of the substrate
+}
+
+// CPPN inputs are x1, y1, x2, y2 and the distance between the two points
+const CPPN_INPUTS: usize = 5;
+// CPPN outputs are link weight and node bias
+const CPPN_OUTPUTS: usize = 2;
+
+// Radar and agent sensor quadrants in the order forward, right, back, left, using the angles of the rangefinders
+static RADAR_ANGLES: [f64; RADAR_AMOUNT] = [0.0, 270.0, 180.0, 90.0];
+
+/// Positions of the controller nodes in the agent frame, facing along the x axis.
+/// Rangefinders lie on the unit circle at their angles, radar inputs at half radius,
+/// agent sensors outside the rangefinders, hidden nodes on a ring between them and the two outputs close to the centre.
+struct Substrate {
+    input_ids: Vec<usize>, // Network input read by each input node
+    inputs: Vec<(f64, f64)>,
+    hidden_nodes: Vec<(f64, f64)>,
+    outputs: Vec<(f64, f64)>,
+}
+
+impl Substrate {
+    fn agent(morphology: &Morphology) -> Substrate {
+        let radar: Vec<(usize, f64)> = if morphology.radar {
+            morphology
+                .radar_inputs()
+                .zip(RADAR_ANGLES.iter().cloned())
+                .collect()
+        } else {
+            vec![]
+        };
+        let agent_sensors: Vec<(usize, f64)> = if morphology.agent_sensors {
+            morphology
+                .agent_sensor_inputs()
+                .zip(RADAR_ANGLES.iter().cloned())
+                .collect()
+        } else {
+            vec![]
+        };
+
+        let (input_ids, inputs) = morphology
+            .rangefinders
+            .iter()
+            .map(|rangefinder| (rangefinder.input, point_at(rangefinder.angle, 1.0)))
+            .chain(
+                radar
+                    .iter()
+                    .map(|(input, angle)| (*input, point_at(*angle, 0.5))),
+            )
+            .chain(
+                agent_sensors
+                    .iter()
+                    .map(|(input, angle)| (*input, point_at(*angle, 1.25))),
+            )
+            .unzip();
+
+        let hidden_amount = config::AGENT.substrate_hidden_nodes;
+        let hidden_nodes = (0..hidden_amount)
+            .map(|i| point_at(360.0 * i as f64 / hidden_amount as f64, 0.75))
+            .collect();
+
+        Substrate {
+            input_ids,
+            inputs,
+            hidden_nodes,
+            outputs: vec![point_at(0.0, 0.25), point_at(90.0, 0.25)], // Velocity, angular velocity
+        }
+    }
+
+    /// Queries the CPPN for every possible link and the bias of every non-input node
+    fn decode(&self, cppn: &mut NeuralNetwork) -> NeuralNetwork {
+        let input_amount = self.inputs.len();
+        let hidden_end = input_amount + self.hidden_nodes.len();
+        let mut links: Vec<(usize, usize, f64)> = vec![];
+
+        let targets: Vec<(usize, &(f64, f64))> = (input_amount..)
+            .zip(self.hidden_nodes.iter())
+            .chain((hidden_end..).zip(self.outputs.iter()))
+            .collect();
+
+        for (from, from_point) in self.inputs.iter().enumerate() {
+            for (to, to_point) in targets.iter() {
+                if let Some(weight) = express_weight(query(cppn, from_point, to_point)[0]) {
+                    links.push((from, *to, weight));
+                }
+            }
+        }
+
+        for (from, from_point) in (input_amount..).zip(self.hidden_nodes.iter()) {
+            for (to, to_point) in (hidden_end..).zip(self.outputs.iter()) {
+                if let Some(weight) = express_weight(query(cppn, from_point, to_point)[0]) {
+                    links.push((from, to, weight));
+                }
+            }
+        }
+
+        let hidden_nodes: Vec<_> = self
+            .hidden_nodes
+            .iter()
+            .map(|point| {
+                (
+                    express_bias(query(cppn, &(0.0, 0.0), point)[1]),
+                    config::AGENT.substrate_hidden_activation,
+                )
+            })
+            .collect();
+
+        let outputs: Vec<_> = self
+            .outputs
+            .iter()
+            .map(|point| {
+                (
+                    express_bias(query(cppn, &(0.0, 0.0), point)[1]),
+                    config::AGENT.substrate_output_activation,
+                )
+            })
+            .collect();
+
+        NeuralNetwork::layered(&self.input_ids, &hidden_nodes, &outputs, &links)
+    }
+}
+
+fn point_at(angle: f64, radius: f64) -> (f64, f64) {
+    let radians = angle.to_radians();
+    (radius * radians.cos(), radius * radians.sin())
+}
+
+fn query(cppn: &mut NeuralNetwork, from: &(f64, f64), to: &(f64, f64)) -> [f64; CPPN_OUTPUTS] {
+    let distance = ((to.0 - from.0).powi(2) + (to.1 - from.1).powi(2)).sqrt();
+
+    // Every query is independent, so recurrent and plastic state must not carry over
+    cppn.reset();
+    let outputs = cppn.activate(&[from.0, from.1, to.0, to.1, distance]);
+
+    [outputs[0], outputs[1]]
+}
+
+// Outputs below the threshold are not expressed, the rest is scaled to the maximum weight
+fn express_weight(output: f64) -> Option<f64> {
+    let threshold = config::AGENT.substrate_weight_threshold;
+    let output = output.clamp(-1.0, 1.0);
+
+    if output.abs() < threshold {
+        return None;
+    }
+
+    let scaled = (output.abs() - threshold) / (1.0 - threshold);
+    Some(output.signum() * scaled * config::AGENT.substrate_max_weight)
+}
+
+fn express_bias(output: f64) -> f64 {
+    output.clamp(-1.0, 1.0) * config::AGENT.substrate_max_weight
+}
+
+/// Inputs and outputs of genomes for a sensor layout under the configured encoding
+pub fn genome_size(morphology: &Morphology) -> (usize, usize) {
+    match config::AGENT.encoding {
+        Encoding::Direct => (morphology.input_size(), 2),
+        Encoding::Cppn => (CPPN_INPUTS, CPPN_OUTPUTS),
+    }
+}
+
+/// Controller of an agent genome and sensor layout under the configured encoding
+pub fn build_network(genome: &Genome, morphology: &Morphology) -> NeuralNetwork {
+    match config::AGENT.encoding {
+        Encoding::Direct => NeuralNetwork::new(genome),
+        Encoding::Cppn => Substrate::agent(morphology).decode(&mut NeuralNetwork::new(genome)),
+    }
+}
+
+impl str::FromStr for Encoding {
+    type Err = u64;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Direct" => Ok(Encoding::Direct),
+            "Cppn" => Ok(Encoding::Cppn),
+            _ => Err(1),
+        }
+    }
+}
@@ -1,40 +1,93 @@
+use std::collections::VecDeque;
+
 use crate::config;
+use crate::neatns::behaviour::Behaviour;
+use crate::neatns::spatial_grid::SpatialGrid;
 use crate::simulator::Point;
 
-pub struct NoveltyArchive {
-    pub(crate) novelty_items: Vec<Point>,
+/// Archive of novel behaviours.
+/// Behaviours are added when their novelty exceeds a threshold, which is raised when many
+/// behaviours are added in one generation and lowered when none are added for a while.
+pub struct NoveltyArchive<B: Behaviour> {
+    novelty_items: VecDeque<(u64, Point)>, // Id and position of every behaviour, oldest first
+    index: SpatialGrid<B>,
+    next_id: u64,
+    pub(crate) threshold: f64,
+    added_this_generation: usize,
+    generations_without_additions: usize,
 }
 
-impl NoveltyArchive {
-    pub fn new() -> NoveltyArchive {
+impl<B: Behaviour> NoveltyArchive<B> {
+    pub fn new() -> NoveltyArchive<B> {
         NoveltyArchive {
-            novelty_items: vec![],
+            novelty_items: VecDeque::new(),
+            index: SpatialGrid::new(config::NEATNS.archive_cell_size),
+            next_id: 0,
+            threshold: config::NEATNS.novelty_threshold,
+            added_this_generation: 0,
+            generations_without_additions: 0,
         }
     }
 
-    pub fn add_or_discard_position(&mut self, position: Point) {
-        self.novelty_items.push(position);
+    /// Adds the behaviour if it is novel enough, dropping the oldest entry when the archive is full
+    pub fn add_or_discard_behaviour(&mut self, behaviour: B, novelty: f64) {
+        if novelty < self.threshold {
+            return;
+        }
+
+        let size_limit = config::NEATNS.archive_size_limit;
+        if size_limit > 0 && self.novelty_items.len() >= size_limit {
+            if let Some((id, position)) = self.novelty_items.pop_front() {
+                self.index.remove(id, &position);
+            }
+        }
+
+        self.novelty_items
+            .push_back((self.next_id, behaviour.position().clone()));
+        self.index.insert(self.next_id, behaviour);
+        self.next_id += 1;
+        self.added_this_generation += 1;
     }
 
-    pub fn evaluate_position_novelty(&self, position: &Point) -> f64 {
-        let mut distances = self.find_distances_to_point(position);
+    /// Mean distance to the nearest neighbours among the current population and the archive.
+    /// 'population' is an index of the behaviours of the current generation, including the
+    /// evaluated agent, whose own zero distance is skipped.
+    pub fn evaluate_behaviour_novelty(&self, behaviour: &B, population: &SpatialGrid<B>) -> f64 {
+        let k = config::NEATNS.amount_of_neighbors;
+
+        let mut distances = population.nearest_distances(behaviour, k + 1);
+        if !distances.is_empty() {
+            distances.remove(0);
+        }
+        distances.extend(self.index.nearest_distances(behaviour, k));
         distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        distances.truncate(k);
 
-        if distances.len() < 15 {
+        if distances.is_empty() {
             return 0.0;
         }
 
-        let closest_points = distances[0..config::NEATNS.amount_of_neighbors].to_vec();
-        closest_points.iter().sum::<f64>() / closest_points.len() as f64
+        distances.iter().sum::<f64>() / distances.len() as f64
     }
 
-    pub fn find_distances_to_point(&self, current_point: &Point) -> Vec<f64> {
-        let mut distances: Vec<f64> = vec![];
+    /// Adapts the threshold to the number of positions added during the generation
+    pub fn end_generation(&mut self) {
+        if self.added_this_generation > config::NEATNS.archive_add_limit {
+            self.threshold *= 1.2;
+        }
 
-        for point in self.novelty_items.iter() {
-            distances.push(euclidean_distance(point, current_point));
+        if self.added_this_generation == 0 {
+            self.generations_without_additions += 1;
+        } else {
+            self.generations_without_additions = 0;
         }
-        distances
+
+        if self.generations_without_additions >= config::NEATNS.archive_stagnation_generations {
+            self.threshold = (self.threshold * 0.95).max(config::NEATNS.novelty_threshold_min);
+            self.generations_without_additions = 0;
+        }
+
+        self.added_this_generation = 0;
     }
 }
 
@@ -5,23 +5,30 @@ use rand::Rng;
 use crate::config;
 use crate::maze::maze_phenotype::MazePhenotype;
 use crate::neatns::agent::Agent;
+use crate::neatns::behaviour::Behaviour;
 use crate::neatns::network::innovation::InnovationLog;
 use crate::neatns::network::innovation::InnovationTime;
 use crate::neatns::novelty_archive::NoveltyArchive;
+use crate::neatns::search::{objective_fitness, SearchMode};
+use crate::neatns::spatial_grid::SpatialGrid;
 use crate::neatns::species::Species;
+use crate::simulator::morphology::Morphology;
+use crate::simulator::pool::run_parallel;
 use crate::simulator::simulate_single_neatns;
 
-pub struct Population {
+pub struct Population<B: Behaviour> {
     population_size: usize,
     species: Vec<Species>,
     pub innovation_log: InnovationLog,
     pub global_innovation: InnovationTime,
-    pub novelty_archive: NoveltyArchive,
+    pub novelty_archive: NoveltyArchive<B>,
     total_individuals_added: u32,
+    speciation_threshold: f64,
+    pub speciation_history: Vec<(usize, f64)>, // Species count and threshold after every generation
 }
 
-impl Population {
-    pub fn new(population_size: usize, inputs: usize, outputs: usize) -> Population {
+impl<B: Behaviour> Population<B> {
+    pub fn new(population_size: usize, morphology: &Morphology) -> Population<B> {
         let mut population = Population {
             population_size,
             species: Vec::new(),
@@ -29,11 +36,13 @@ impl Population {
             global_innovation: InnovationTime::new(),
             novelty_archive: NoveltyArchive::new(),
             total_individuals_added: 0,
+            speciation_threshold: config::NEAT.speciation_threshold,
+            speciation_history: vec![],
         };
 
         for _ in 0..population_size {
             population.push(
-                Agent::new(0, inputs, outputs, population.total_individuals_added),
+                Agent::new(0, morphology, population.total_individuals_added),
                 false,
             );
             population.total_individuals_added += 1;
@@ -58,8 +67,10 @@ impl Population {
 
     /// Find first species compatible with agent
     fn compatible_species(&mut self, agent: &Agent) -> Option<&mut Species> {
+        let threshold = self.speciation_threshold;
+
         for species in self.species.iter_mut() {
-            if species.is_compatible(&agent) {
+            if species.is_compatible(agent, threshold) {
                 return Some(species);
             }
         }
@@ -195,6 +206,28 @@ impl Population {
 
         // Verify correct number of individuals in new population
         assert_eq!(self.iter().count(), config::NEAT.population_size);
+
+        self.adjust_speciation_threshold();
+        self.speciation_history
+            .push((self.species.len(), self.speciation_threshold));
+    }
+
+    /// Moves the threshold a step towards the target species count, if one is set.
+    /// Fewer species than the target lowers the threshold, which splits agents into more species.
+    fn adjust_speciation_threshold(&mut self) {
+        let target = config::NEAT.target_species;
+
+        if target == 0 {
+            return;
+        }
+
+        let step = config::NEAT.speciation_threshold_step;
+        if self.species.len() < target {
+            self.speciation_threshold =
+                (self.speciation_threshold - step).max(config::NEAT.speciation_threshold_min);
+        } else if self.species.len() > target {
+            self.speciation_threshold += step;
+        }
     }
 
     /// Get random agent from population
@@ -232,34 +265,100 @@ impl Population {
         self.species.iter().map(|species| species.iter()).flatten()
     }
 
+    /// Simulate all agents in parallel, then update novelty sequentially in population order,
+    /// so that archive updates and the reported solver do not depend on thread timing.
+    /// Fitness is then set according to the configured search mode.
     pub fn run_simulation_and_update_fitness(
         &mut self,
         maze: &MazePhenotype,
-        path_length: u32,
+        step_budget: u32,
     ) -> Option<Agent> {
-        for species in self.species.iter_mut() {
-            for agent in species.agents.iter_mut() {
-                let result = simulate_single_neatns(agent, &maze, path_length, false);
+        let outcomes = {
+            let agents: Vec<&Agent> = self
+                .species
+                .iter()
+                .flat_map(|species| species.agents.iter())
+                .collect();
+
+            run_parallel(
+                agents.len(),
+                || (),
+                |_, index| {
+                    let result =
+                        simulate_single_neatns(agents[index], maze, step_budget, B::NEEDS_PATH);
+                    let objective = result
+                        .final_position
+                        .as_ref()
+                        .map_or(0.0, |position| objective_fitness(position, maze));
+                    (
+                        result.agent_reached_end(),
+                        B::from_result(&result, maze, step_budget),
+                        objective,
+                    )
+                },
+            )
+        };
 
-                if result.agent_reached_end() {
-                    let final_position = result.final_position.unwrap();
+        // Novelty is measured against the whole current generation as well as the archive
+        let mut population_behaviours = SpatialGrid::new(config::NEATNS.archive_cell_size);
+        for (id, (_, behaviour, _)) in outcomes.iter().enumerate() {
+            if let Some(behaviour) = behaviour {
+                population_behaviours.insert(id as u64, behaviour.clone());
+            }
+        }
 
-                    //println!("final position: {}", final_position);
-                    self.novelty_archive.add_or_discard_position(final_position);
+        let mode = config::NEATNS.search_mode;
+        let mut novelties: Vec<f64> = vec![0.0; outcomes.len()];
+        let mut index = 0;
 
-                    return Some(agent.clone());
+        for species in self.species.iter() {
+            for agent in species.agents.iter() {
+                let (reached_end, behaviour, _) = &outcomes[index];
+
+                if let Some(behaviour) = behaviour {
+                    if mode.uses_novelty() {
+                        novelties[index] = self
+                            .novelty_archive
+                            .evaluate_behaviour_novelty(behaviour, &population_behaviours);
+                        self.novelty_archive
+                            .add_or_discard_behaviour(behaviour.clone(), novelties[index]);
+                    }
+
+                    if *reached_end {
+                        return Some(agent.clone());
+                    }
                 }
+                index += 1;
+            }
+        }
 
-                if result.final_position.is_some() {
-                    let final_position = result.final_position.unwrap();
-                    let fitness = self
-                        .novelty_archive
-                        .evaluate_position_novelty(&final_position);
+        if mode.uses_novelty() {
+            self.novelty_archive.end_generation();
+        }
+
+        let max_novelty = novelties.iter().cloned().fold(0.0, f64::max);
+        let mut index = 0;
 
-                    //println!("fitness: {} | position: {}", fitness, final_position);
-                    agent.fitness = fitness;
-                    self.novelty_archive.add_or_discard_position(final_position);
+        for species in self.species.iter_mut() {
+            for agent in species.agents.iter_mut() {
+                let (_, behaviour, objective) = &outcomes[index];
+
+                if let Some(behaviour) = behaviour {
+                    let novelty = if mode == SearchMode::Novelty || max_novelty <= 0.0 {
+                        novelties[index]
+                    } else {
+                        novelties[index] / max_novelty
+                    };
+
+                    let local_competition = if mode == SearchMode::LocalCompetition {
+                        local_competition(index, behaviour, &population_behaviours, &outcomes)
+                    } else {
+                        0.0
+                    };
+
+                    agent.fitness = mode.fitness(novelty, *objective, local_competition);
                 }
+                index += 1;
             }
         }
 
@@ -267,7 +366,37 @@ impl Population {
     }
 }
 
-impl fmt::Display for Population {
+/// Share of the nearest behavioural neighbours in the population with a lower objective
+fn local_competition<B: Behaviour>(
+    index: usize,
+    behaviour: &B,
+    population_behaviours: &SpatialGrid<B>,
+    outcomes: &[(bool, Option<B>, f64)],
+) -> f64 {
+    let k = config::NEATNS.amount_of_neighbors;
+    let objective = outcomes[index].2;
+
+    let neighbours: Vec<u64> = population_behaviours
+        .nearest(behaviour, k + 1)
+        .into_iter()
+        .map(|(_, id)| id)
+        .filter(|id| *id != index as u64)
+        .take(k)
+        .collect();
+
+    if neighbours.is_empty() {
+        return 0.0;
+    }
+
+    let beaten = neighbours
+        .iter()
+        .filter(|id| outcomes[**id as usize].2 < objective)
+        .count();
+
+    beaten as f64 / neighbours.len() as f64
+}
+
+impl<B: Behaviour> fmt::Display for Population<B> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Population(species: {}): ", self.species.len())?;
         for species in self.species.iter() {
@@ -0,0 +1,108 @@
+use std::str;
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use rand::Rng;
+
+use crate::config;
+use crate::maze::maze_phenotype::MazePhenotype;
+use crate::neatns::novelty_archive::euclidean_distance;
+use crate::simulator::Point;
+
+/// What seed search selects for
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SearchMode {
+    Objective,        // Closeness of the final position to the goal
+    Novelty,          // Novelty of the behaviour
+    Blend,            // Weighted sum of normalized novelty and objective
+    LocalCompetition, // Normalized novelty plus the share of behavioural neighbours beaten
+    Random,           // Random fitness
+}
+
+impl SearchMode {
+    pub fn uses_novelty(&self) -> bool {
+        match self {
+            SearchMode::Novelty | SearchMode::Blend | SearchMode::LocalCompetition => true,
+            SearchMode::Objective | SearchMode::Random => false,
+        }
+    }
+
+    /// Fitness of an agent, 'novelty' is normalized to the most novel agent of the generation and
+    /// 'local_competition' is the share of nearest neighbours with a lower objective
+    pub fn fitness(&self, novelty: f64, objective: f64, local_competition: f64) -> f64 {
+        match self {
+            SearchMode::Objective => objective,
+            SearchMode::Novelty => novelty,
+            SearchMode::Blend => {
+                let weight = config::NEATNS.novelty_weight;
+                weight * novelty + (1.0 - weight) * objective
+            }
+            SearchMode::LocalCompetition => novelty + local_competition,
+            SearchMode::Random => rand::thread_rng().gen::<f64>(),
+        }
+    }
+}
+
+/// Closeness of the position to the goal, 1.0 at the goal and 0.0 a maze diagonal away
+pub fn objective_fitness(position: &Point, maze: &MazePhenotype) -> f64 {
+    let goal = Point {
+        x: maze.width as f64 - 0.5,
+        y: 0.5,
+    };
+    let diagonal = ((maze.width as f64).powi(2) + (maze.height as f64).powi(2)).sqrt();
+
+    (1.0 - euclidean_distance(position, &goal) / diagonal).max(0.0)
+}
+
+/// Outcome of one seed search on one maze
+#[derive(Clone, Debug)]
+pub struct SeedSearch {
+    pub maze_width: u32,
+    pub maze_height: u32,
+    pub generations: usize, // Generations until solved, or the generation limit
+    pub solved: bool,
+    pub speciation_history: Vec<(usize, f64)>, // Species count and threshold after every generation
+}
+
+impl SeedSearch {
+    pub fn to_whitespace_separated_string(&self) -> String {
+        format!(
+            "{:?} {:?} {} {} {} {}",
+            config::NEATNS.search_mode,
+            config::NEATNS.behaviour,
+            self.maze_width,
+            self.maze_height,
+            self.generations,
+            self.solved
+        )
+    }
+}
+
+lazy_static! {
+    // Seed searches since the last report, from all threads
+    static ref SEED_SEARCHES: Mutex<Vec<SeedSearch>> = Mutex::new(vec![]);
+}
+
+pub fn record_seed_search(search: SeedSearch) {
+    SEED_SEARCHES.lock().unwrap().push(search);
+}
+
+/// Returns and forgets all recorded seed searches
+pub fn take_seed_searches() -> Vec<SeedSearch> {
+    std::mem::take(&mut *SEED_SEARCHES.lock().unwrap())
+}
+
+impl str::FromStr for SearchMode {
+    type Err = u64;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Objective" => Ok(SearchMode::Objective),
+            "Novelty" => Ok(SearchMode::Novelty),
+            "Blend" => Ok(SearchMode::Blend),
+            "LocalCompetition" => Ok(SearchMode::LocalCompetition),
+            "Random" => Ok(SearchMode::Random),
+            _ => Err(1),
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use crate::neatns::behaviour::Behaviour;
+use crate::simulator::Point;
+
+/// Uniform grid of behaviours, placed by their positions, for nearest neighbour queries.
+/// Queries search rings of cells outwards from the cell of the query behaviour,
+/// and stop once no unvisited cell can hold a closer behaviour.
+pub struct SpatialGrid<B: Behaviour> {
+    cell_size: f64,
+    cells: HashMap<(i64, i64), Vec<(u64, B)>>,
+    len: usize,
+    // Bounds of all cells ever used, limits the search when there are few behaviours
+    min_cell: (i64, i64),
+    max_cell: (i64, i64),
+}
+
+impl<B: Behaviour> SpatialGrid<B> {
+    pub fn new(cell_size: f64) -> SpatialGrid<B> {
+        SpatialGrid {
+            cell_size,
+            cells: HashMap::new(),
+            len: 0,
+            min_cell: (0, 0),
+            max_cell: (0, 0),
+        }
+    }
+
+    fn cell_of(&self, point: &Point) -> (i64, i64) {
+        (
+            (point.x / self.cell_size).floor() as i64,
+            (point.y / self.cell_size).floor() as i64,
+        )
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Inserts the behaviour, 'id' is only used to remove it again
+    pub fn insert(&mut self, id: u64, behaviour: B) {
+        let cell = self.cell_of(behaviour.position());
+
+        if self.cells.is_empty() {
+            self.min_cell = cell;
+            self.max_cell = cell;
+        } else {
+            self.min_cell = (self.min_cell.0.min(cell.0), self.min_cell.1.min(cell.1));
+            self.max_cell = (self.max_cell.0.max(cell.0), self.max_cell.1.max(cell.1));
+        }
+
+        self.cells.entry(cell).or_default().push((id, behaviour));
+        self.len += 1;
+    }
+
+    /// Removes the behaviour inserted with 'id' at 'position', returns whether it was found
+    pub fn remove(&mut self, id: u64, position: &Point) -> bool {
+        let cell = self.cell_of(position);
+
+        if let Some(entries) = self.cells.get_mut(&cell) {
+            if let Some(index) = entries.iter().position(|(entry_id, _)| *entry_id == id) {
+                entries.swap_remove(index);
+                self.len -= 1;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Distances to the 'k' closest behaviours, sorted closest first.
+    /// Fewer are returned when the grid holds fewer than 'k' behaviours.
+    pub fn nearest_distances(&self, behaviour: &B, k: usize) -> Vec<f64> {
+        self.nearest(behaviour, k)
+            .into_iter()
+            .map(|(distance, _)| distance)
+            .collect()
+    }
+
+    /// Distances and ids of the 'k' closest behaviours, sorted closest first
+    pub fn nearest(&self, behaviour: &B, k: usize) -> Vec<(f64, u64)> {
+        let mut distances: Vec<(f64, u64)> = vec![];
+
+        if k == 0 || self.len == 0 {
+            return distances;
+        }
+
+        let center = self.cell_of(behaviour.position());
+        // Rings beyond this cover no used cells
+        let max_ring = [
+            center.0 - self.min_cell.0,
+            self.max_cell.0 - center.0,
+            center.1 - self.min_cell.1,
+            self.max_cell.1 - center.1,
+        ]
+        .iter()
+        .cloned()
+        .max()
+        .unwrap_or(0)
+        .max(0);
+
+        for ring in 0..=max_ring {
+            for x in center.0 - ring..=center.0 + ring {
+                for y in center.1 - ring..=center.1 + ring {
+                    if (x - center.0).abs() != ring && (y - center.1).abs() != ring {
+                        continue;
+                    }
+
+                    if let Some(entries) = self.cells.get(&(x, y)) {
+                        distances.extend(
+                            entries
+                                .iter()
+                                .map(|(id, other)| (behaviour.distance(other), *id)),
+                        );
+                    }
+                }
+            }
+
+            // Positions outside the searched rings are at least 'ring' cells away
+            if distances.len() >= k {
+                distances.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+                distances.truncate(k);
+
+                let bound = ring as f64 * self.cell_size * behaviour.position_bound();
+                if bound > 0.0 && distances[k - 1].0 <= bound {
+                    return distances;
+                }
+            }
+        }
+
+        distances.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        distances.truncate(k);
+        distances
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neatns::behaviour::FinalPosition;
+
+    fn behaviour(x: f64, y: f64) -> FinalPosition {
+        FinalPosition::new(Point { x, y })
+    }
+
+    fn brute_force(behaviours: &[FinalPosition], query: &FinalPosition, k: usize) -> Vec<f64> {
+        let mut distances: Vec<f64> = behaviours.iter().map(|b| query.distance(b)).collect();
+        distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        distances.truncate(k);
+        distances
+    }
+
+    #[test]
+    fn nearest_distances_match_brute_force() {
+        let behaviours: Vec<FinalPosition> = (0..200)
+            .map(|i| {
+                behaviour(
+                    ((i * 37) % 101) as f64 * 0.13,
+                    ((i * 53) % 89) as f64 * 0.17,
+                )
+            })
+            .collect();
+
+        let mut grid = SpatialGrid::new(1.0);
+        for (id, b) in behaviours.iter().enumerate() {
+            grid.insert(id as u64, b.clone());
+        }
+
+        for query in behaviours.iter().step_by(7) {
+            assert_eq!(
+                grid.nearest_distances(query, 15),
+                brute_force(&behaviours, query, 15)
+            );
+        }
+    }
+
+    #[test]
+    fn returns_all_behaviours_when_fewer_than_k() {
+        let mut grid = SpatialGrid::new(0.5);
+        grid.insert(0, behaviour(0.0, 0.0));
+        grid.insert(1, behaviour(10.0, -4.0));
+
+        assert_eq!(grid.nearest_distances(&behaviour(0.0, 0.0), 5).len(), 2);
+    }
+
+    #[test]
+    fn removed_behaviours_are_not_found() {
+        let mut grid = SpatialGrid::new(1.0);
+        grid.insert(0, behaviour(1.0, 1.0));
+        grid.insert(1, behaviour(3.0, 1.0));
+
+        assert!(grid.remove(0, &Point { x: 1.0, y: 1.0 }));
+        assert!(!grid.remove(0, &Point { x: 1.0, y: 1.0 }));
+        assert_eq!(grid.len(), 1);
+        assert_eq!(grid.nearest_distances(&behaviour(1.0, 1.0), 1), vec![2.0]);
+    }
+}
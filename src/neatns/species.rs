@@ -32,9 +32,9 @@ impl Species {
     }
 
     /// Determine wether a new agent is compatible
-    pub fn is_compatible(&mut self, other: &Agent) -> bool {
+    pub fn is_compatible(&mut self, other: &Agent, threshold: f64) -> bool {
         if let Some(agent) = self.agents.first() {
-            agent.distance(other) < config::NEAT.speciation_threshold
+            agent.distance(other) < threshold
         } else {
             true // All agents are compatible if the species is empty
         }
@@ -0,0 +1,68 @@
+use crate::config;
+use crate::neatns::novelty_archive::euclidean_distance;
+use crate::simulator::run_state::RunState;
+use crate::simulator::Point;
+
+/// Proximity of the closest other agent in each quadrant around the agent.
+/// 1.0 when touching, 0.0 when out of range or no agent is present.
+#[derive(Debug, Clone)]
+pub struct AgentSensorValues {
+    forward: f64,
+    right: f64,
+    back: f64,
+    left: f64,
+}
+
+impl AgentSensorValues {
+    pub fn new() -> AgentSensorValues {
+        AgentSensorValues {
+            forward: 0.0,
+            right: 0.0,
+            back: 0.0,
+            left: 0.0,
+        }
+    }
+
+    pub fn write_to(&self, values: &mut [f64]) {
+        values[0] = self.forward;
+        values[1] = self.right;
+        values[2] = self.back;
+        values[3] = self.left;
+    }
+}
+
+pub fn get_agent_sensor_values(run_state: &RunState, others: &[Point]) -> AgentSensorValues {
+    let mut values = AgentSensorValues::new();
+    let position = Point::new(run_state.global_x, run_state.global_y);
+
+    for other in others.iter() {
+        let distance = euclidean_distance(&position, other);
+        let proximity = 1.0 - distance / config::SIMULATOR.agent_sensor_range;
+
+        if proximity <= 0.0 {
+            continue;
+        }
+
+        let angle = (other.y - position.y)
+            .atan2(other.x - position.x)
+            .to_degrees();
+        let difference = (angle - run_state.current_direction).rem_euclid(360.0);
+
+        // Same quadrants as the radar, counter clockwise from heading
+        let quadrant = if !(45.0..315.0).contains(&difference) {
+            &mut values.forward
+        } else if difference < 135.0 {
+            &mut values.left
+        } else if difference < 225.0 {
+            &mut values.back
+        } else {
+            &mut values.right
+        };
+
+        if proximity > *quadrant {
+            *quadrant = proximity;
+        }
+    }
+
+    values
+}
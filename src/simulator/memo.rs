@@ -0,0 +1,118 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+
+use crate::config;
+use crate::maze::maze_genotype::MazeGenome;
+use crate::mcc::agent::mcc_agent::MCCAgent;
+use crate::simulator::step_budget::get_step_budget;
+use crate::simulator::{simulate_single_mcc, SimulatorResult};
+
+// Outcomes of solving agent/maze pairs, the only pairs read back by analytics
+struct Memo {
+    results: HashMap<(u32, u32), SimulatorResult>, // Keyed by (agent id, maze id)
+    keys: VecDeque<(u32, u32)>,                    // In the order they were stored
+}
+
+lazy_static! {
+    static ref MEMO: Mutex<Memo> = Mutex::new(Memo {
+        results: HashMap::new(),
+        keys: VecDeque::new(),
+    });
+}
+
+pub fn get_result(agent_id: u32, maze_id: u32) -> Option<SimulatorResult> {
+    if !config::SIMULATOR.memoize_evaluations {
+        return None;
+    }
+
+    MEMO.lock()
+        .unwrap()
+        .results
+        .get(&(agent_id, maze_id))
+        .cloned()
+}
+
+/// Stores the outcome if the agent solved the maze. Once more than the configured capacity
+/// is stored, the oldest outcomes are forgotten.
+pub fn store_result(agent_id: u32, maze_id: u32, result: SimulatorResult) {
+    if !config::SIMULATOR.memoize_evaluations || !result.agent_reached_end {
+        return;
+    }
+
+    let mut memo = MEMO.lock().unwrap();
+    let key = (agent_id, maze_id);
+
+    if memo.results.insert(key, result).is_none() {
+        memo.keys.push_back(key);
+    }
+
+    while memo.keys.len() > config::SIMULATOR.memo_capacity {
+        if let Some(oldest) = memo.keys.pop_front() {
+            memo.results.remove(&oldest);
+        }
+    }
+}
+
+/// Forgets all stored outcomes, called at the start of every run
+pub fn clear_results() {
+    let mut memo = MEMO.lock().unwrap();
+    memo.results.clear();
+    memo.keys.clear();
+}
+
+/// Returns the stored outcome of the pair, or simulates it and stores the result.
+/// Outcomes from 'simulate_many' carry their path, others stored without one are
+/// simulated again when a path is asked for.
+pub fn simulate_memoized(agent: &MCCAgent, maze: &MazeGenome, trace_path: bool) -> SimulatorResult {
+    if let Some(result) = get_result(agent.id, maze.id) {
+        if !trace_path || !result.agent_path.is_empty() {
+            return result;
+        }
+    }
+
+    let maze_phenotype = maze.get_phenotype();
+    let result = simulate_single_mcc(
+        agent,
+        maze_phenotype,
+        get_step_budget(maze, maze_phenotype),
+        trace_path,
+    );
+
+    store_result(agent.id, maze.id, result.clone());
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(reached_end: bool) -> SimulatorResult {
+        let mut result = SimulatorResult::new();
+        result.set_agent_reached_end(reached_end);
+        result
+    }
+
+    #[test]
+    fn keeps_only_recent_solving_pairs() {
+        let maze_id = u32::MAX;
+        let capacity = config::SIMULATOR.memo_capacity as u32;
+        clear_results();
+
+        store_result(0, maze_id, result(false));
+        assert!(get_result(0, maze_id).is_none());
+
+        for agent_id in 0..=capacity {
+            store_result(agent_id, maze_id, result(true));
+        }
+
+        assert!(get_result(0, maze_id).is_none());
+        assert!(get_result(1, maze_id).is_some());
+        assert!(get_result(capacity, maze_id).is_some());
+
+        clear_results();
+        assert!(get_result(capacity, maze_id).is_none());
+    }
+}
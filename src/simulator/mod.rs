@@ -1,16 +1,35 @@
-use std::{fmt, thread};
+use std::fmt;
 
 use crate::config;
 use crate::maze::maze_genotype::MazeGenome;
 use crate::maze::maze_phenotype::MazePhenotype;
 use crate::mcc::agent::mcc_agent::MCCAgent;
 use crate::neatns::agent::Agent;
-use crate::simulator::radar::get_radar_values;
+use crate::neatns::network::neural_network::NeuralNetwork;
+use crate::simulator::memo::store_result;
+use crate::simulator::morphology::Morphology;
+use crate::simulator::noise::Noise;
+use crate::simulator::pool::run_parallel;
+use crate::simulator::radar::{get_radar_values, RADAR_AMOUNT};
+use crate::simulator::refinement::{closeness, refine_near_misses};
 use crate::simulator::run_state::RunState;
-
+use crate::simulator::step_budget::get_step_budget;
+use crate::simulator::stuck::{AgentState, ExitReason, StuckDetector};
+use crate::simulator::trace::TraceStep;
+
+pub mod agent_sensor;
+pub mod memo;
+pub mod morphology;
+pub mod multi_agent;
+pub mod noise;
+pub mod pool;
 pub mod radar;
+mod refinement;
 mod run_state;
 mod sensor;
+pub mod step_budget;
+pub mod stuck;
+pub mod trace;
 
 #[derive(Debug, Clone)]
 pub struct Point {
@@ -35,6 +54,10 @@ pub struct SimulatorResult {
     pub(crate) agent_reached_end: bool,
     pub(crate) agent_path: Vec<Point>,
     pub(crate) final_position: Option<Point>,
+    pub(crate) trace: Vec<TraceStep>,
+    pub(crate) exit_reason: ExitReason,
+    pub(crate) steps_used: u32,
+    pub(crate) noise_seed: u64, // Seed of the noise the run was simulated under
 }
 
 impl SimulatorResult {
@@ -43,6 +66,10 @@ impl SimulatorResult {
             agent_reached_end: false,
             agent_path: vec![],
             final_position: Option::None,
+            trace: vec![],
+            exit_reason: ExitReason::TimeLimit,
+            steps_used: 0,
+            noise_seed: 0,
         }
     }
 
@@ -57,14 +84,18 @@ impl SimulatorResult {
     pub fn add_point(&mut self, point: Point) {
         self.agent_path.push(point);
     }
+
+    pub fn add_trace_step(&mut self, step: TraceStep) {
+        self.trace.push(step);
+    }
 }
 
 impl fmt::Display for SimulatorResult {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Simulator result: \nCompleted: {} \nPath: {:?}",
-            self.agent_reached_end, self.agent_path
+            "Simulator result: \nCompleted: {} \nExit reason: {} \nPath: {:?}",
+            self.agent_reached_end, self.exit_reason, self.agent_path
         )
     }
 }
@@ -72,64 +103,126 @@ impl fmt::Display for SimulatorResult {
 pub fn simulate_single_neatns(
     agent: &Agent,
     maze: &MazePhenotype,
-    length: u32,
+    step_budget: u32,
     trace_path: bool,
 ) -> SimulatorResult {
-    let mut steps_left = length * config::MAZE.cell_dimension as u32;
-    let mut run_state = RunState::new(maze.height);
-
     let mut agent_phenotype = agent.to_phenotype();
 
-    let mut result = SimulatorResult::new();
-
-    while steps_left > 0 {
-        let sensor_values = run_state.get_all_sensor_values(maze);
-        let radar_values = get_radar_values(&run_state, maze).to_f64_vector();
-        let all_inputs = [&sensor_values[..], &radar_values[..]].concat();
-
-        let output = agent_phenotype.activate(&all_inputs);
-        run_state.update_velocities(output[0], output[1]);
-        let new_position = run_state.update_position(maze);
+    run_simulation(
+        &mut agent_phenotype,
+        &agent.morphology,
+        maze,
+        step_budget,
+        trace_path,
+        false,
+        &mut Noise::random(),
+    )
+}
 
-        if trace_path {
-            result.add_point(new_position.clone());
-        }
+pub fn simulate_single_mcc(
+    agent: &MCCAgent,
+    maze: &MazePhenotype,
+    step_budget: u32,
+    trace_path: bool,
+) -> SimulatorResult {
+    simulate_single_mcc_with_noise(agent, maze, step_budget, trace_path, &mut Noise::random())
+}
 
-        if run_state.maze_completed(maze.width) {
-            result.final_position = Option::Some(new_position.clone());
-            result.set_agent_reached_end(true);
-            return result;
-        }
+pub fn simulate_single_mcc_with_noise(
+    agent: &MCCAgent,
+    maze: &MazePhenotype,
+    step_budget: u32,
+    trace_path: bool,
+    noise: &mut Noise,
+) -> SimulatorResult {
+    let mut agent_phenotype = agent.to_phenotype();
 
-        steps_left -= 1;
+    run_simulation(
+        &mut agent_phenotype,
+        &agent.morphology,
+        maze,
+        step_budget,
+        trace_path,
+        false,
+        noise,
+    )
+}
 
-        if steps_left == 0 {
-            result.final_position = Option::Some(new_position.clone());
-        }
-    }
+// Simulates the agent while recording the full state of every step.
+// Runs under the noise of 'noise_seed', or without noise if it is None.
+pub fn trace_single_mcc(
+    agent: &MCCAgent,
+    maze: &MazePhenotype,
+    step_budget: u32,
+    noise_seed: Option<u64>,
+) -> SimulatorResult {
+    let mut agent_phenotype = agent.to_phenotype();
 
-    result
+    run_simulation(
+        &mut agent_phenotype,
+        &agent.morphology,
+        maze,
+        step_budget,
+        true,
+        true,
+        &mut noise_seed.map_or_else(Noise::none, Noise::new),
+    )
 }
 
-pub fn simulate_single_mcc(
-    agent: &MCCAgent,
+fn run_simulation(
+    network: &mut NeuralNetwork,
+    morphology: &Morphology,
     maze: &MazePhenotype,
-    length: u32,
+    step_budget: u32,
     trace_path: bool,
+    record_trace: bool,
+    noise: &mut Noise,
 ) -> SimulatorResult {
-    let mut steps_left = length * config::MAZE.cell_dimension as u32;
+    let mut steps_left = step_budget;
     let mut run_state = RunState::new(maze.height);
 
-    let mut agent_phenotype = agent.to_phenotype();
-
     let mut result = SimulatorResult::new();
+    result.noise_seed = noise.seed();
+    let mut stuck_detector = StuckDetector::new();
+
+    // A repeated state only implies that the run repeats itself when nothing else carries state
+    let check_stuck = stuck_detector.is_enabled()
+        && network.is_stateless()
+        && !noise.is_enabled()
+        && !record_trace;
+
+    let mut step = 0;
+
+    // Buffers reused every step, network inputs are placed by the morphology
+    let mut sensor_values = vec![0.0; morphology.rangefinders.len()];
+    let mut radar_values = [0.0; RADAR_AMOUNT];
+    let mut inputs = vec![0.0; morphology.input_size()];
+    let mut output = [0.0; 2];
 
     while steps_left > 0 {
-        let sensor_values = run_state.get_all_sensor_values(maze);
-        let radar_values = get_radar_values(&run_state, maze).to_f64_vector();
-        let all_inputs = [&sensor_values[..], &radar_values[..]].concat();
+        morphology.write_sensor_values(&run_state, maze, &mut sensor_values);
+        if morphology.radar {
+            get_radar_values(&run_state, maze).write_to(&mut radar_values);
+        }
+
+        if noise.is_enabled() {
+            noise.apply_to_sensors(&mut sensor_values);
+            if morphology.radar {
+                noise.apply_to_radar(&mut radar_values);
+            }
+        }
+
+        morphology.write_inputs(&sensor_values, &radar_values, &mut inputs);
+        output.copy_from_slice(network.activate(&inputs));
+        let raw_output = if record_trace { output.to_vec() } else { vec![] };
+        config::SIMULATOR
+            .output_squashing
+            .apply(&mut output, config::SIMULATOR.output_limit);
+
+        if noise.is_enabled() {
+            noise.apply_to_motors(&mut output);
+        }
 
-        let output = agent_phenotype.activate(&all_inputs);
         run_state.update_velocities(output[0], output[1]);
 
         let new_position = run_state.update_position(maze);
@@ -138,12 +231,55 @@ pub fn simulate_single_mcc(
             result.add_point(new_position.clone());
         }
 
+        if record_trace {
+            result.add_trace_step(TraceStep {
+                step,
+                x: new_position.x,
+                y: new_position.y,
+                heading: run_state.current_direction,
+                velocity: run_state.current_velocity,
+                angular_velocity: run_state.current_angular_velocity,
+                sensors: sensor_values.clone(),
+                radar: if morphology.radar {
+                    radar_values.to_vec()
+                } else {
+                    vec![]
+                },
+                outputs: raw_output,
+                collided: run_state.collided,
+            });
+        }
+        step += 1;
+        result.steps_used = step;
+
         if run_state.maze_completed(maze.width) {
             result.final_position = Option::Some(new_position.clone());
             result.set_agent_reached_end(true);
+            result.exit_reason = ExitReason::Completed;
             return result;
         }
 
+        if check_stuck {
+            if let Some(reason) = stuck_detector.update(AgentState::new(&run_state)) {
+                // The remaining steps repeat the detected cycle, so they are filled in without simulating
+                let remaining = steps_left - 1;
+                if trace_path {
+                    for state in stuck_detector.repeat(remaining) {
+                        result.add_point(state.position());
+                    }
+                }
+
+                result.final_position = Option::Some(
+                    stuck_detector
+                        .repeat(remaining)
+                        .last()
+                        .map_or(new_position.clone(), |state| state.position()),
+                );
+                result.exit_reason = reason;
+                return result;
+            }
+        }
+
         steps_left -= 1;
 
         if steps_left == 0 {
@@ -154,46 +290,97 @@ pub fn simulate_single_mcc(
     result
 }
 
+// Re-runs the agent in the maze under a set of fixed noise seeds.
+// Returns the fraction of runs where the agent reached the end.
+pub fn evaluate_robustness(agent: &MCCAgent, maze: &MazeGenome, seeds: u32) -> f64 {
+    if seeds == 0 {
+        return 0.0;
+    }
+
+    let maze_phenotype = maze.get_phenotype();
+    let step_budget = get_step_budget(maze, maze_phenotype);
+    let mut successes = 0;
+
+    for seed in 0..seeds {
+        let result = simulate_single_mcc_with_noise(
+            agent,
+            maze_phenotype,
+            step_budget,
+            false,
+            &mut Noise::new(seed as u64),
+        );
+
+        if result.agent_reached_end {
+            successes += 1;
+        }
+    }
+
+    successes as f64 / seeds as f64
+}
+
 // Simulates each agent in all mazes, marks viable agents and mazes that fulfill MC
+// Networks and maze phenotypes are built once and shared by a bounded set of worker threads.
+// Solving pairs are stored for analytics, after the parallel evaluation.
+// Agents that solve no maze may then have their weights refined, if enabled.
 pub fn simulate_many(agents: &mut Vec<MCCAgent>, mazes: &mut Vec<MazeGenome>) {
-    let mut threads = vec![];
-
-    for (i, maze) in mazes.iter().enumerate() {
-        let maze_clone = maze.clone();
-        let agents_clone = agents.clone();
-
-        threads.push(thread::spawn(move || {
-            let maze_phenotype = maze_clone.to_phenotype();
-            let mut viable_agents: Vec<(usize, usize)> = vec![];
-
-            for (j, agent) in agents_clone.iter().enumerate() {
-                let simulator_result = simulate_single_mcc(
-                    agent,
-                    &maze_phenotype,
-                    maze_clone.get_solution_path_cell_length(),
-                    false,
-                );
-                if simulator_result.agent_reached_end {
-                    viable_agents.push((j, i));
-                }
-            }
+    let step_budgets: Vec<u32> = mazes
+        .iter()
+        .map(|maze| get_step_budget(maze, maze.get_phenotype()))
+        .collect();
+
+    let agent_amount = agents.len();
+    // Stored outcomes carry their path, so analytics can reuse them instead of simulating again
+    let trace_path = config::SIMULATOR.memoize_evaluations;
+
+    // Activation writes to internal buffers, so each worker copies the network of an agent
+    // the first time it simulates that agent, and reuses the copy for its other mazes.
+    // Outcomes are the closeness of every pair, and the result if the agent solved the maze.
+    let outcomes: Vec<(f64, Option<SimulatorResult>)> = run_parallel(
+        agent_amount * mazes.len(),
+        || vec![None; agent_amount],
+        |networks: &mut Vec<Option<NeuralNetwork>>, index| {
+            let (agent_index, maze_index) = (index % agent_amount, index / agent_amount);
+
+            let network =
+                networks[agent_index].get_or_insert_with(|| agents[agent_index].to_phenotype());
+            network.reset();
+
+            let simulator_result = run_simulation(
+                network,
+                &agents[agent_index].morphology,
+                mazes[maze_index].get_phenotype(),
+                step_budgets[maze_index],
+                trace_path,
+                false,
+                &mut Noise::random(),
+            );
+            (
+                closeness(&simulator_result, &mazes[maze_index]),
+                Some(simulator_result).filter(|result| result.agent_reached_end),
+            )
+        },
+    );
 
-            return viable_agents;
-        }));
-    }
+    let mut closeness = Vec::with_capacity(outcomes.len());
+
+    for (index, (pair_closeness, solved)) in outcomes.into_iter().enumerate() {
+        closeness.push(pair_closeness);
 
-    let mut all_viable_pairs: Vec<(usize, usize)> = vec![];
+        if let Some(simulator_result) = solved {
+            let (agent_index, maze_index) = (index % agent_amount, index / agent_amount);
 
-    for child in threads {
-        let viable_pairs = child.join();
-        all_viable_pairs.append(&mut viable_pairs.unwrap());
+            agents[agent_index].viable = true;
+            agents[agent_index].completed_maze_id = Some(mazes[maze_index].id);
+            mazes[maze_index].viable = true;
+            mazes[maze_index].successful_agent_id = Some(agents[agent_index].id);
+            mazes[maze_index].successful_noise_seed = Some(simulator_result.noise_seed);
+
+            store_result(agents[agent_index].id, mazes[maze_index].id, simulator_result);
+        }
     }
 
-    for viable_pair in all_viable_pairs.iter() {
-        agents[viable_pair.0].viable = true;
-        agents[viable_pair.0].completed_maze_id = Some(mazes[viable_pair.1].id);
-        mazes[viable_pair.1].viable = true;
-        mazes[viable_pair.1].successful_agent_id = Some(agents[viable_pair.0].id)
+    if config::AGENT.refinement_evaluations > 0 {
+        refine_near_misses(agents, mazes, &step_budgets, &closeness);
     }
 }
 
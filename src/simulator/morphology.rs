@@ -0,0 +1,193 @@
+use rand::Rng;
+
+use crate::config;
+use crate::maze::maze_phenotype::MazePhenotype;
+use crate::neatns::network::genome::Genome;
+use crate::neatns::network::substrate::Encoding;
+use crate::simulator::multi_agent::{AGENT_SENSOR_INPUTS, AGENT_SENSOR_START};
+use crate::simulator::radar::RADAR_AMOUNT;
+use crate::simulator::run_state::{RunState, SENSOR_AMOUNT, SENSOR_BASE_ANGLES};
+
+// Inputs up to here belong to the default rangefinders, the radar and the agent sensors
+const FIRST_ADDED_INPUT: usize = AGENT_SENSOR_START + AGENT_SENSOR_INPUTS;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rangefinder {
+    pub input: usize, // Network input the rangefinder writes to
+    pub angle: f64,   // Degrees relative to the heading
+}
+
+/// Sensor layout of an agent. Rangefinders keep their network input when others are added
+/// or removed, and the radar always uses the inputs following the default rangefinders.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Morphology {
+    pub rangefinders: Vec<Rangefinder>,
+    pub radar: bool,
+    pub agent_sensors: bool, // Senses other agents, only used by the multi-agent experiment
+}
+
+impl Morphology {
+    /// The fixed layout of agents without evolved sensors
+    pub fn new() -> Morphology {
+        Morphology {
+            rangefinders: SENSOR_BASE_ANGLES
+                .iter()
+                .enumerate()
+                .map(|(input, angle)| Rangefinder {
+                    input,
+                    angle: *angle,
+                })
+                .collect(),
+            radar: true,
+            agent_sensors: false,
+        }
+    }
+
+    /// The fixed layout with inputs for sensing other agents sharing the maze
+    pub fn with_agent_sensors() -> Morphology {
+        Morphology {
+            agent_sensors: true,
+            ..Morphology::new()
+        }
+    }
+
+    pub fn radar_inputs(&self) -> std::ops::Range<usize> {
+        SENSOR_AMOUNT..SENSOR_AMOUNT + RADAR_AMOUNT
+    }
+
+    pub fn agent_sensor_inputs(&self) -> std::ops::Range<usize> {
+        AGENT_SENSOR_START..AGENT_SENSOR_START + AGENT_SENSOR_INPUTS
+    }
+
+    /// Length of an input buffer that holds every sensor of this layout
+    pub fn input_size(&self) -> usize {
+        let radar_end = if self.radar {
+            self.radar_inputs().end
+        } else {
+            0
+        };
+        let agent_sensor_end = if self.agent_sensors {
+            self.agent_sensor_inputs().end
+        } else {
+            0
+        };
+
+        self.rangefinders
+            .iter()
+            .map(|rangefinder| rangefinder.input + 1)
+            .max()
+            .unwrap_or(0)
+            .max(radar_end)
+            .max(agent_sensor_end)
+    }
+
+    /// Number of network inputs fed by sensors
+    pub fn sensor_amount(&self) -> usize {
+        self.rangefinders.len()
+            + if self.radar { RADAR_AMOUNT } else { 0 }
+            + if self.agent_sensors {
+                AGENT_SENSOR_INPUTS
+            } else {
+                0
+            }
+    }
+
+    /// Writes one value per rangefinder into 'values', in the order of the rangefinders
+    pub fn write_sensor_values(
+        &self,
+        run_state: &RunState,
+        maze: &MazePhenotype,
+        values: &mut [f64],
+    ) {
+        for (value, rangefinder) in values.iter_mut().zip(self.rangefinders.iter()) {
+            *value = run_state.get_sensor_value(
+                (rangefinder.angle + run_state.current_direction) % 360.0,
+                maze,
+            );
+        }
+    }
+
+    /// Copies sensor and radar values to the network inputs of their sensors
+    pub fn write_inputs(&self, sensor_values: &[f64], radar_values: &[f64], inputs: &mut [f64]) {
+        for (value, rangefinder) in sensor_values.iter().zip(self.rangefinders.iter()) {
+            inputs[rangefinder.input] = *value;
+        }
+
+        if self.radar {
+            inputs[self.radar_inputs()].copy_from_slice(radar_values);
+        }
+    }
+
+    /// Mutates the sensor layout. Under the direct encoding the genome gains or loses
+    /// input nodes to match, under the CPPN encoding the substrate follows the layout.
+    pub fn mutate(&mut self, genome: &mut Genome) {
+        let mut rng = rand::thread_rng();
+        let direct = config::AGENT.encoding == Encoding::Direct;
+
+        if rng.gen::<f64>() < config::AGENT.add_rangefinder
+            && self.rangefinders.len() < config::AGENT.max_rangefinders
+        {
+            let input = self.input_size().max(FIRST_ADDED_INPUT);
+            self.rangefinders.push(Rangefinder {
+                input,
+                angle: rng.gen_range(0.0, 360.0),
+            });
+
+            if direct {
+                genome.add_input(input as u64);
+            }
+        }
+
+        if rng.gen::<f64>() < config::AGENT.remove_rangefinder && self.rangefinders.len() > 1 {
+            let rangefinder = self
+                .rangefinders
+                .remove(rng.gen_range(0, self.rangefinders.len()));
+
+            if direct {
+                genome.remove_input(rangefinder.input as u64);
+            }
+        }
+
+        if rng.gen::<f64>() < config::AGENT.mutate_rangefinder_angle {
+            let size = config::AGENT.rangefinder_angle_size;
+            let index = rng.gen_range(0, self.rangefinders.len());
+            let angle = self.rangefinders[index].angle + rng.gen_range(-size, size);
+
+            self.rangefinders[index].angle = angle.rem_euclid(360.0);
+        }
+
+        if rng.gen::<f64>() < config::AGENT.toggle_radar {
+            self.radar = !self.radar;
+
+            if direct {
+                for input in self.radar_inputs() {
+                    if self.radar {
+                        genome.add_input(input as u64);
+                    } else {
+                        genome.remove_input(input as u64);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agent_sensors_extend_inputs() {
+        assert_eq!(Morphology::new().input_size(), AGENT_SENSOR_START);
+
+        let mut morphology = Morphology::with_agent_sensors();
+        assert_eq!(morphology.input_size(), FIRST_ADDED_INPUT);
+        assert_eq!(
+            morphology.sensor_amount(),
+            SENSOR_AMOUNT + RADAR_AMOUNT + AGENT_SENSOR_INPUTS
+        );
+
+        morphology.radar = false;
+        assert_eq!(morphology.input_size(), FIRST_ADDED_INPUT);
+    }
+}
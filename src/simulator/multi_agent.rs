@@ -0,0 +1,199 @@
+use crate::config;
+use crate::maze::maze_genotype::MazeGenome;
+use crate::maze::maze_phenotype::MazePhenotype;
+use crate::mcc::agent::mcc_agent::MCCAgent;
+use crate::neatns::network::neural_network::NetworkBatch;
+use crate::neatns::novelty_archive::euclidean_distance;
+use crate::simulator::agent_sensor::get_agent_sensor_values;
+use crate::simulator::noise::Noise;
+use crate::simulator::pool::run_parallel;
+use crate::simulator::radar::{get_radar_values, RADAR_AMOUNT};
+use crate::simulator::run_state::{RunState, SENSOR_AMOUNT};
+use crate::simulator::step_budget::get_step_budget;
+use crate::simulator::stuck::ExitReason;
+use crate::simulator::{Point, SimulatorResult};
+
+/// Number of extra inputs given to agents with agent sensors for sensing the other agents
+pub const AGENT_SENSOR_INPUTS: usize = 4;
+
+/// The agent sensors follow the inputs of the default rangefinders and the radar
+pub const AGENT_SENSOR_START: usize = SENSOR_AMOUNT + RADAR_AMOUNT;
+
+// Splits the agents into groups that share each maze, marks agents that reach the end
+// while sharing the maze, and the mazes they solve, as viable
+pub fn simulate_many_multi(agents: &mut [MCCAgent], mazes: &mut [MazeGenome]) {
+    let group_size = config::SIMULATOR.multi_agent_group_size.max(1);
+    let group_amount = agents.len().div_ceil(group_size);
+
+    let outcomes: Vec<Vec<SimulatorResult>> = {
+        let (agents, mazes) = (&*agents, &*mazes);
+
+        run_parallel(
+            group_amount * mazes.len(),
+            || (),
+            |_, index| {
+                let (group_index, maze_index) = (index % group_amount, index / group_amount);
+                let group_start = group_index * group_size;
+                let group = &agents[group_start..(group_start + group_size).min(agents.len())];
+                let maze = mazes[maze_index].get_phenotype();
+
+                simulate_multi_mcc(
+                    group,
+                    maze,
+                    get_step_budget(&mazes[maze_index], maze),
+                    false,
+                )
+            },
+        )
+    };
+
+    for (index, results) in outcomes.iter().enumerate() {
+        let (group_index, maze_index) = (index % group_amount, index / group_amount);
+
+        for (offset, _) in results
+            .iter()
+            .enumerate()
+            .filter(|(_, result)| result.agent_reached_end)
+        {
+            let agent_index = group_index * group_size + offset;
+
+            agents[agent_index].viable = true;
+            agents[agent_index].completed_maze_id = Some(mazes[maze_index].id);
+            mazes[maze_index].viable = true;
+            mazes[maze_index].successful_agent_id = Some(agents[agent_index].id);
+        }
+    }
+}
+
+// Simulates all agents at the same time in the same maze.
+// Every step all agents sense and activate together, then move in turn.
+// Agents leave the maze when they reach the end.
+pub fn simulate_multi_mcc(
+    agents: &[MCCAgent],
+    maze: &MazePhenotype,
+    step_budget: u32,
+    trace_path: bool,
+) -> Vec<SimulatorResult> {
+    let networks = agents.iter().map(|agent| agent.to_phenotype()).collect();
+    let input_size = agents
+        .iter()
+        .map(|agent| agent.morphology.input_size())
+        .max()
+        .unwrap_or(0);
+    let mut batch = NetworkBatch::new(networks, input_size);
+    let mut sensor_values: Vec<Vec<f64>> = agents
+        .iter()
+        .map(|agent| vec![0.0; agent.morphology.rangefinders.len()])
+        .collect();
+    let mut radar_values = [0.0; RADAR_AMOUNT];
+    let mut run_states: Vec<RunState> = agents.iter().map(|_| RunState::new(maze.height)).collect();
+    let mut results: Vec<SimulatorResult> = agents.iter().map(|_| SimulatorResult::new()).collect();
+    let mut active: Vec<bool> = vec![true; agents.len()];
+    let mut noise = Noise::random();
+
+    let collision_distance = 2.0 * config::AGENT.agent_radius / config::MAZE.cell_dimension;
+
+    for step in 0..step_budget {
+        if !active.iter().any(|a| *a) {
+            break;
+        }
+
+        for i in 0..agents.len() {
+            if !active[i] {
+                continue;
+            }
+
+            let others = other_positions(&run_states, &active, i);
+            let morphology = &agents[i].morphology;
+            let sensor_values = &mut sensor_values[i];
+
+            morphology.write_sensor_values(&run_states[i], maze, sensor_values);
+            if morphology.radar {
+                get_radar_values(&run_states[i], maze).write_to(&mut radar_values);
+            }
+
+            if noise.is_enabled() {
+                noise.apply_to_sensors(sensor_values);
+                if morphology.radar {
+                    noise.apply_to_radar(&mut radar_values);
+                }
+            }
+
+            let inputs = batch.input(i);
+            morphology.write_inputs(sensor_values, &radar_values, inputs);
+            if morphology.agent_sensors {
+                get_agent_sensor_values(&run_states[i], &others)
+                    .write_to(&mut inputs[morphology.agent_sensor_inputs()]);
+            }
+        }
+
+        batch.activate(&active);
+
+        for i in 0..agents.len() {
+            if !active[i] {
+                continue;
+            }
+
+            let others = other_positions(&run_states, &active, i);
+            let output = batch.output(i);
+            config::SIMULATOR
+                .output_squashing
+                .apply(output, config::SIMULATOR.output_limit);
+
+            if noise.is_enabled() {
+                noise.apply_to_motors(output);
+            }
+
+            run_states[i].update_velocities(output[0], output[1]);
+
+            let previous_state = run_states[i].clone();
+            let mut new_position = run_states[i].update_position(maze);
+
+            // Moves that bring two agents closer than their radii are blocked.
+            // Moves that separate overlapping agents are allowed, as all agents start at the same place.
+            if config::SIMULATOR.agent_collisions {
+                let previous_position =
+                    Point::new(previous_state.global_x, previous_state.global_y);
+
+                let collides = others.iter().any(|other| {
+                    let distance = euclidean_distance(&new_position, other);
+                    distance < collision_distance
+                        && distance < euclidean_distance(&previous_position, other)
+                });
+
+                if collides {
+                    run_states[i] = previous_state;
+                    run_states[i].collided = true;
+                    new_position = previous_position;
+                }
+            }
+
+            let result = &mut results[i];
+
+            if trace_path {
+                result.add_point(new_position.clone());
+            }
+            result.steps_used = step + 1;
+
+            if run_states[i].maze_completed(maze.width) {
+                result.final_position = Option::Some(new_position);
+                result.set_agent_reached_end(true);
+                result.exit_reason = ExitReason::Completed;
+                active[i] = false;
+            } else if step + 1 == step_budget {
+                result.final_position = Option::Some(new_position);
+            }
+        }
+    }
+
+    results
+}
+
+fn other_positions(run_states: &[RunState], active: &[bool], index: usize) -> Vec<Point> {
+    run_states
+        .iter()
+        .enumerate()
+        .filter(|(j, _)| *j != index && active[*j])
+        .map(|(_, state)| Point::new(state.global_x, state.global_y))
+        .collect()
+}
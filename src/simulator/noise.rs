@@ -0,0 +1,105 @@
+use std::f64::consts::PI;
+use std::str;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::config;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NoiseType {
+    None,
+    Gaussian,
+    Uniform,
+}
+
+/// Seeded source of sensor, radar and motor noise for a single simulation.
+/// The same seed always gives the same perturbations, so noisy runs can be repeated.
+pub struct Noise {
+    noise_type: NoiseType,
+    rng: StdRng,
+    seed: u64,
+}
+
+impl Noise {
+    pub fn new(seed: u64) -> Noise {
+        Noise {
+            noise_type: config::SIMULATOR.noise_type,
+            rng: StdRng::seed_from_u64(seed),
+            seed,
+        }
+    }
+
+    /// Noise that leaves every value unchanged, whatever the configured noise type
+    pub fn none() -> Noise {
+        Noise {
+            noise_type: NoiseType::None,
+            rng: StdRng::seed_from_u64(0),
+            seed: 0,
+        }
+    }
+
+    pub fn random() -> Noise {
+        Noise::new(rand::thread_rng().gen())
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.noise_type != NoiseType::None
+    }
+
+    pub fn apply_to_sensors(&mut self, values: &mut [f64]) {
+        for value in values.iter_mut() {
+            *value += self.sample(config::SIMULATOR.sensor_noise);
+
+            // Rangefinders can not measure negative distances
+            if *value < 0.0 {
+                *value = 0.0;
+            }
+        }
+    }
+
+    pub fn apply_to_radar(&mut self, values: &mut [f64]) {
+        for value in values.iter_mut() {
+            *value += self.sample(config::SIMULATOR.radar_noise);
+        }
+    }
+
+    pub fn apply_to_motors(&mut self, values: &mut [f64]) {
+        for value in values.iter_mut() {
+            *value += self.sample(config::SIMULATOR.motor_noise);
+        }
+    }
+
+    /// Sample noise with the given magnitude.
+    /// Magnitude is the standard deviation for gaussian noise, and the half width for uniform noise.
+    fn sample(&mut self, magnitude: f64) -> f64 {
+        match self.noise_type {
+            NoiseType::None => 0.0,
+            NoiseType::Gaussian => {
+                // Box-Muller transform, 1 - u avoids ln(0)
+                let u1: f64 = 1.0 - self.rng.gen::<f64>();
+                let u2: f64 = self.rng.gen::<f64>();
+
+                (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos() * magnitude
+            }
+            NoiseType::Uniform => (self.rng.gen::<f64>() - 0.5) * 2.0 * magnitude,
+        }
+    }
+}
+
+impl str::FromStr for NoiseType {
+    type Err = u64;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "None" => Ok(NoiseType::None),
+            "Gaussian" => Ok(NoiseType::Gaussian),
+            "Uniform" => Ok(NoiseType::Uniform),
+            _ => Err(1),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::config;
+
+/// Number of worker threads used for simulations
+pub fn get_thread_amount() -> usize {
+    if config::SIMULATOR.simulation_threads > 0 {
+        config::SIMULATOR.simulation_threads
+    } else {
+        thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    }
+}
+
+/// Runs 'job' for every index in 0..amount on a bounded set of worker threads.
+/// Every worker creates its own state once with 'init', and pulls the next index when done.
+/// Results are returned in index order, independent of thread timing.
+pub fn run_parallel<S, T, I, F>(amount: usize, init: I, job: F) -> Vec<T>
+where
+    T: Send,
+    I: Fn() -> S + Sync,
+    F: Fn(&mut S, usize) -> T + Sync,
+{
+    let next_index = AtomicUsize::new(0);
+    let results: Mutex<Vec<(usize, T)>> = Mutex::new(Vec::with_capacity(amount));
+    let threads = get_thread_amount().min(amount).max(1);
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                let mut state = init();
+                let mut finished: Vec<(usize, T)> = vec![];
+
+                loop {
+                    let index = next_index.fetch_add(1, Ordering::Relaxed);
+                    if index >= amount {
+                        break;
+                    }
+                    finished.push((index, job(&mut state, index)));
+                }
+
+                results.lock().unwrap().append(&mut finished);
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results_in_index_order() {
+        let results = run_parallel(
+            100,
+            || 0,
+            |calls, index| {
+                *calls += 1;
+                index * 2
+            },
+        );
+
+        assert_eq!(results, (0..100).map(|i| i * 2).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn test_no_jobs() {
+        let results: Vec<usize> = run_parallel(0, || (), |_, index| index);
+
+        assert!(results.is_empty());
+    }
+}
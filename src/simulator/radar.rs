@@ -19,16 +19,16 @@ impl RadarValues {
         }
     }
 
-    pub fn to_f64_vector(&self) -> Vec<f64> {
-        vec![
-            if self.forward { 1.0 } else { 0.0 },
-            if self.right { 1.0 } else { 0.0 },
-            if self.back { 1.0 } else { 0.0 },
-            if self.left { 1.0 } else { 0.0 },
-        ]
+    pub fn write_to(&self, values: &mut [f64]) {
+        values[0] = if self.forward { 1.0 } else { 0.0 };
+        values[1] = if self.right { 1.0 } else { 0.0 };
+        values[2] = if self.back { 1.0 } else { 0.0 };
+        values[3] = if self.left { 1.0 } else { 0.0 };
     }
 }
 
+pub(crate) const RADAR_AMOUNT: usize = 4;
+
 pub fn get_radar_values(run_state: &RunState, maze: &MazePhenotype) -> RadarValues {
     let mut radar_values = RadarValues::new();
 
@@ -0,0 +1,141 @@
+use crate::config;
+use crate::maze::maze_genotype::MazeGenome;
+use crate::mcc::agent::mcc_agent::MCCAgent;
+use crate::neatns::network::genome::Genome;
+use crate::neatns::network::substrate::build_network;
+use crate::neatns::search::objective_fitness;
+use crate::simulator::memo::store_result;
+use crate::simulator::morphology::Morphology;
+use crate::simulator::noise::Noise;
+use crate::simulator::pool::run_parallel;
+use crate::simulator::{run_simulation, SimulatorResult};
+
+// Outcome of hill-climbing the weights of one agent on one maze
+struct Refinement {
+    genome: Option<Genome>, // Best weights found, if better than the original
+    result: Option<SimulatorResult>, // Outcome of the weights that solved the maze
+    evaluations: u32,
+}
+
+/// How close the agent ended to the goal of the maze, 1.0 at the goal
+pub(crate) fn closeness(result: &SimulatorResult, maze: &MazeGenome) -> f64 {
+    result.final_position.as_ref().map_or(0.0, |position| {
+        objective_fitness(position, maze.get_phenotype())
+    })
+}
+
+/// Hill-climbs the link weights of agents that solved no maze, on the maze each came closest
+/// to solving. 'closeness' holds the closeness of every agent/maze pair, indexed like in
+/// simulate_many. Improved weights are written back, and agents that come to solve the maze
+/// are marked viable together with it.
+pub(crate) fn refine_near_misses(
+    agents: &mut [MCCAgent],
+    mazes: &mut [MazeGenome],
+    step_budgets: &[u32],
+    closeness: &[f64],
+) {
+    let agent_amount = agents.len();
+
+    // The closest maze of every agent that is a near miss
+    let candidates: Vec<(usize, usize, f64)> = (0..agent_amount)
+        .filter(|agent_index| !agents[*agent_index].viable)
+        .filter_map(|agent_index| {
+            (0..mazes.len())
+                .map(|maze_index| {
+                    (
+                        agent_index,
+                        maze_index,
+                        closeness[maze_index * agent_amount + agent_index],
+                    )
+                })
+                .max_by(|a, b| a.2.partial_cmp(&b.2).unwrap())
+        })
+        .filter(|(_, _, closeness)| *closeness >= config::AGENT.refinement_min_closeness)
+        .collect();
+
+    let refinements = {
+        let (agents, mazes) = (&*agents, &*mazes);
+
+        run_parallel(
+            candidates.len(),
+            || (),
+            |_, index| {
+                let (agent_index, maze_index, closeness) = candidates[index];
+                hill_climb(
+                    &agents[agent_index].genome,
+                    &agents[agent_index].morphology,
+                    &mazes[maze_index],
+                    step_budgets[maze_index],
+                    closeness,
+                )
+            },
+        )
+    };
+
+    for ((agent_index, maze_index, _), refinement) in candidates.iter().zip(refinements) {
+        let agent = &mut agents[*agent_index];
+        agent.refinement_evaluations = refinement.evaluations;
+
+        if let Some(genome) = refinement.genome {
+            agent.set_refined_genome(genome);
+        }
+
+        if let Some(result) = refinement.result {
+            let maze = &mut mazes[*maze_index];
+            maze.successful_noise_seed = Some(result.noise_seed);
+            store_result(agent.id, maze.id, result);
+
+            agent.viable = true;
+            agent.completed_maze_id = Some(maze.id);
+            maze.viable = true;
+            maze.successful_agent_id = Some(agent.id);
+        }
+    }
+}
+
+// Perturbs all weights of the best genome so far, keeping perturbations that end closer to the goal
+fn hill_climb(
+    genome: &Genome,
+    morphology: &Morphology,
+    maze: &MazeGenome,
+    step_budget: u32,
+    closeness: f64,
+) -> Refinement {
+    let mut refinement = Refinement {
+        genome: None,
+        result: None,
+        evaluations: 0,
+    };
+    let mut best_closeness = closeness;
+
+    while refinement.evaluations < config::AGENT.refinement_evaluations {
+        let mut candidate = refinement.genome.as_ref().unwrap_or(genome).clone();
+        candidate.perturb_link_weights(config::AGENT.refinement_weight_size);
+
+        let mut network = build_network(&candidate, morphology);
+        let result = run_simulation(
+            &mut network,
+            morphology,
+            maze.get_phenotype(),
+            step_budget,
+            false,
+            false,
+            &mut Noise::random(),
+        );
+        refinement.evaluations += 1;
+
+        if result.agent_reached_end {
+            refinement.genome = Some(candidate);
+            refinement.result = Some(result);
+            break;
+        }
+
+        let candidate_closeness = self::closeness(&result, maze);
+        if candidate_closeness > best_closeness {
+            best_closeness = candidate_closeness;
+            refinement.genome = Some(candidate);
+        }
+    }
+
+    refinement
+}
@@ -7,6 +7,7 @@ use crate::simulator::sensor::{
 use crate::simulator::Point;
 use std::f64::consts::PI;
 
+#[derive(Clone)]
 pub struct RunState {
     pub(crate) global_x: f64,
     pub(crate) global_y: f64,
@@ -17,6 +18,7 @@ pub struct RunState {
     pub(crate) current_direction: f64,
     pub(crate) current_velocity: f64,
     pub(crate) current_angular_velocity: f64,
+    pub(crate) collided: bool,
 }
 
 impl RunState {
@@ -31,6 +33,7 @@ impl RunState {
             current_direction: config::AGENT.start_offset,
             current_velocity: 0.0,
             current_angular_velocity: 0.0,
+            collided: false,
         }
     }
 
@@ -94,9 +97,12 @@ impl RunState {
             new_current_y_in_cell,
             maze,
         ) {
+            self.collided = true;
             return Point::new(self.global_x, self.global_y); // return same position as last time step
         }
 
+        self.collided = false;
+
         // Find next agent's location
         self.global_x = new_global_x;
         self.global_y = new_global_y;
@@ -184,16 +190,8 @@ impl RunState {
             1.0
         }
     }
-
-    pub fn get_all_sensor_values(&self, maze: &MazePhenotype) -> Vec<f64> {
-        let mut sensor_values: Vec<f64> = Vec::new();
-
-        for angle in SENSOR_BASE_ANGLES {
-            let value = self.get_sensor_value((angle + self.current_direction) % 360.0, maze);
-            sensor_values.push(value);
-        }
-        sensor_values
-    }
 }
 
-static SENSOR_BASE_ANGLES: &'static [f64] = &[0.0, 45.0, 90.0, 180.0, 270.0, 315.0];
+pub(crate) const SENSOR_AMOUNT: usize = 6;
+
+pub(crate) static SENSOR_BASE_ANGLES: [f64; SENSOR_AMOUNT] = [0.0, 45.0, 90.0, 180.0, 270.0, 315.0];
@@ -0,0 +1,84 @@
+use std::str;
+
+use crate::config;
+use crate::maze::maze_genotype::MazeGenome;
+use crate::maze::maze_phenotype::MazePhenotype;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StepBudgetPolicy {
+    SolutionPath, // Cells along the genome's solution path
+    ShortestPath, // Cells along the BFS shortest path
+    MazeArea,     // Cells in the maze
+    Fixed,        // Fixed number of steps
+    OracleSlack,  // Steps used by an ideal controller
+}
+
+/// Number of steps an agent is given to solve the maze, according to the configured policy
+pub fn get_step_budget(maze: &MazeGenome, maze_phenotype: &MazePhenotype) -> u32 {
+    let multiplier = config::SIMULATOR.step_budget_multiplier;
+    let cell_dimension = config::MAZE.cell_dimension;
+
+    let steps = match config::SIMULATOR.step_budget_policy {
+        StepBudgetPolicy::SolutionPath => {
+            maze.get_solution_path_cell_length() as f64 * cell_dimension * multiplier
+        }
+        StepBudgetPolicy::ShortestPath => {
+            get_shortest_path_cell_length(maze_phenotype) as f64 * cell_dimension * multiplier
+        }
+        StepBudgetPolicy::MazeArea => {
+            (maze_phenotype.width * maze_phenotype.height) as f64 * cell_dimension * multiplier
+        }
+        StepBudgetPolicy::Fixed => config::SIMULATOR.step_budget_fixed_steps as f64,
+        StepBudgetPolicy::OracleSlack => get_oracle_steps(maze_phenotype) as f64 * multiplier,
+    };
+
+    steps as u32
+}
+
+pub fn get_shortest_path_cell_length(maze_phenotype: &MazePhenotype) -> u32 {
+    match maze_phenotype.get_shortest_path() {
+        Some(path) => path.len() as u32 - 1,
+        None => 0,
+    }
+}
+
+/// Steps needed by an ideal controller following the BFS shortest path.
+/// It drives at max speed between cell centres and turns in place at max angular speed.
+pub fn get_oracle_steps(maze_phenotype: &MazePhenotype) -> u32 {
+    let path = match maze_phenotype.get_shortest_path() {
+        Some(path) => path,
+        None => return 0,
+    };
+
+    let mut heading = config::AGENT.start_offset;
+    let mut turning_degrees = 0.0;
+
+    for cells in path.windows(2) {
+        let dx = cells[1].0 as f64 - cells[0].0 as f64;
+        let dy = cells[1].1 as f64 - cells[0].1 as f64;
+        let target_heading = dy.atan2(dx).to_degrees().rem_euclid(360.0);
+
+        let difference = (target_heading - heading).abs() % 360.0;
+        turning_degrees += difference.min(360.0 - difference);
+        heading = target_heading;
+    }
+
+    let distance = (path.len() - 1) as f64 * config::MAZE.cell_dimension;
+
+    ((distance + turning_degrees) / config::AGENT.max_speed).ceil() as u32
+}
+
+impl str::FromStr for StepBudgetPolicy {
+    type Err = u64;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "SolutionPath" => Ok(StepBudgetPolicy::SolutionPath),
+            "ShortestPath" => Ok(StepBudgetPolicy::ShortestPath),
+            "MazeArea" => Ok(StepBudgetPolicy::MazeArea),
+            "Fixed" => Ok(StepBudgetPolicy::Fixed),
+            "OracleSlack" => Ok(StepBudgetPolicy::OracleSlack),
+            _ => Err(1),
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::config;
+use crate::simulator::run_state::RunState;
+use crate::simulator::Point;
+
+/// Why a simulation stopped
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ExitReason {
+    Completed,
+    TimeLimit,
+    NoDisplacement,
+    Oscillation,
+    Spinning,
+}
+
+/// Everything that decides the next step of an agent with a stateless network and no noise
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgentState {
+    x: f64,
+    y: f64,
+    heading: f64,
+    velocity: f64,
+    angular_velocity: f64,
+}
+
+impl AgentState {
+    pub(crate) fn new(run_state: &RunState) -> AgentState {
+        AgentState {
+            x: run_state.global_x,
+            y: run_state.global_y,
+            heading: run_state.current_direction,
+            velocity: run_state.current_velocity,
+            angular_velocity: run_state.current_angular_velocity,
+        }
+    }
+
+    pub fn position(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+}
+
+/// Keeps a sliding window of recent agent states and stops a run once a state repeats exactly.
+/// The simulation is deterministic for a stateless network without noise, so from then on the
+/// agent repeats the same cycle of states until the step budget runs out, without completing.
+pub struct StuckDetector {
+    window: VecDeque<AgentState>, // Oldest state first
+    window_size: usize,           // Longest cycle looked for
+    period: usize,                // Length of the detected cycle
+}
+
+impl StuckDetector {
+    pub fn new() -> StuckDetector {
+        StuckDetector {
+            window: VecDeque::new(),
+            window_size: config::SIMULATOR.stuck_window,
+            period: 0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.window_size > 0
+            && (config::SIMULATOR.exit_on_no_displacement
+                || config::SIMULATOR.exit_on_oscillation
+                || config::SIMULATOR.exit_on_spin)
+    }
+
+    /// Register the state after a step. Returns a reason if the state repeats one in the window.
+    pub fn update(&mut self, state: AgentState) -> Option<ExitReason> {
+        let period = self
+            .window
+            .iter()
+            .rev()
+            .position(|previous| *previous == state)
+            .map(|i| i + 1);
+
+        self.window.push_back(state);
+
+        if self.window.len() > self.window_size {
+            self.window.pop_front();
+        }
+
+        let period = period?;
+        self.period = period;
+
+        let cycle = self.cycle();
+        let first = &cycle[0];
+        let stationary = cycle
+            .iter()
+            .all(|state| state.x == first.x && state.y == first.y);
+
+        let (reason, enabled) = if period == 1 {
+            (
+                ExitReason::NoDisplacement,
+                config::SIMULATOR.exit_on_no_displacement,
+            )
+        } else if stationary {
+            (ExitReason::Spinning, config::SIMULATOR.exit_on_spin)
+        } else {
+            (
+                ExitReason::Oscillation,
+                config::SIMULATOR.exit_on_oscillation,
+            )
+        };
+
+        if enabled {
+            Some(reason)
+        } else {
+            None
+        }
+    }
+
+    // States of the detected cycle, ending with the current state
+    fn cycle(&self) -> Vec<&AgentState> {
+        self.window
+            .iter()
+            .skip(self.window.len() - self.period)
+            .collect()
+    }
+
+    /// States of the 'steps' steps following the current one, once a cycle is detected
+    pub fn repeat(&self, steps: u32) -> impl Iterator<Item = &AgentState> {
+        let cycle = self.cycle();
+        let period = self.period;
+
+        (1..=steps as usize).map(move |step| cycle[(period - 1 + step) % period])
+    }
+}
+
+impl fmt::Display for ExitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitReason::Completed => write!(f, "Completed"),
+            ExitReason::TimeLimit => write!(f, "TimeLimit"),
+            ExitReason::NoDisplacement => write!(f, "NoDisplacement"),
+            ExitReason::Oscillation => write!(f, "Oscillation"),
+            ExitReason::Spinning => write!(f, "Spinning"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(x: f64, y: f64, heading: f64, velocity: f64) -> AgentState {
+        AgentState {
+            x,
+            y,
+            heading,
+            velocity,
+            angular_velocity: 0.0,
+        }
+    }
+
+    fn detector(window_size: usize) -> StuckDetector {
+        StuckDetector {
+            window: VecDeque::new(),
+            window_size,
+            period: 0,
+        }
+    }
+
+    fn positions(detector: &StuckDetector, steps: u32) -> Vec<(f64, f64)> {
+        detector
+            .repeat(steps)
+            .map(|state| (state.x, state.y))
+            .collect()
+    }
+
+    #[test]
+    fn repeated_state_without_movement() {
+        let mut detector = detector(4);
+
+        assert_eq!(detector.update(state(1.5, 2.5, 90.0, 0.0)), None);
+        assert_eq!(
+            detector.update(state(1.5, 2.5, 90.0, 0.0)),
+            Some(ExitReason::NoDisplacement)
+        );
+        assert_eq!(positions(&detector, 2), vec![(1.5, 2.5), (1.5, 2.5)]);
+    }
+
+    #[test]
+    fn repeated_heading_cycle_in_place() {
+        let mut detector = detector(8);
+
+        for heading in [0.0, 90.0, 180.0, 270.0].iter() {
+            assert_eq!(detector.update(state(1.5, 2.5, *heading, 0.0)), None);
+        }
+        assert_eq!(
+            detector.update(state(1.5, 2.5, 0.0, 0.0)),
+            Some(ExitReason::Spinning)
+        );
+        assert_eq!(
+            detector.repeat(5).map(|s| s.heading).collect::<Vec<_>>(),
+            vec![90.0, 180.0, 270.0, 0.0, 90.0]
+        );
+    }
+
+    #[test]
+    fn repeated_positions_back_and_forth() {
+        let mut detector = detector(4);
+
+        assert_eq!(detector.update(state(1.0, 1.0, 0.0, 0.5)), None);
+        assert_eq!(detector.update(state(1.5, 1.0, 180.0, 0.5)), None);
+        assert_eq!(
+            detector.update(state(1.0, 1.0, 0.0, 0.5)),
+            Some(ExitReason::Oscillation)
+        );
+        assert_eq!(
+            positions(&detector, 3),
+            vec![(1.5, 1.0), (1.0, 1.0), (1.5, 1.0)]
+        );
+    }
+
+    #[test]
+    fn different_velocity_is_not_repeated() {
+        let mut detector = detector(4);
+
+        assert_eq!(detector.update(state(1.5, 2.5, 90.0, 0.0)), None);
+        assert_eq!(detector.update(state(1.5, 2.5, 90.0, 0.1)), None);
+        assert_eq!(detector.update(state(1.5, 2.5, 90.0, 0.2)), None);
+    }
+
+    #[test]
+    fn cycle_longer_than_window_is_not_detected() {
+        let mut detector = detector(2);
+
+        for heading in [0.0, 120.0, 240.0, 0.0, 120.0].iter() {
+            assert_eq!(detector.update(state(1.5, 2.5, *heading, 0.0)), None);
+        }
+    }
+}
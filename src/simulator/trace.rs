@@ -0,0 +1,181 @@
+use std::str;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TraceFormat {
+    None,
+    Csv,
+    Json,
+}
+
+/// Complete state of the agent at a single simulation step
+#[derive(Debug, Clone)]
+pub struct TraceStep {
+    pub step: u32,
+    pub x: f64,
+    pub y: f64,
+    pub heading: f64,
+    pub velocity: f64,
+    pub angular_velocity: f64,
+    pub sensors: Vec<f64>,
+    pub radar: Vec<f64>,
+    pub outputs: Vec<f64>, // Network outputs before squashing and noise
+    pub collided: bool,
+}
+
+impl TraceFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TraceFormat::None => "txt",
+            TraceFormat::Csv => "csv",
+            TraceFormat::Json => "json",
+        }
+    }
+}
+
+pub fn trace_to_csv(trace: &[TraceStep]) -> String {
+    let mut text = String::from("step,x,y,heading,velocity,angular_velocity");
+
+    if let Some(first) = trace.first() {
+        for i in 0..first.sensors.len() {
+            text.push_str(&format!(",sensor_{}", i));
+        }
+        for i in 0..first.radar.len() {
+            text.push_str(&format!(",radar_{}", i));
+        }
+        for i in 0..first.outputs.len() {
+            text.push_str(&format!(",output_{}", i));
+        }
+    }
+    text.push_str(",collided\n");
+
+    for step in trace.iter() {
+        text.push_str(&format!(
+            "{},{},{},{},{},{}",
+            step.step, step.x, step.y, step.heading, step.velocity, step.angular_velocity
+        ));
+        for value in step.sensors.iter().chain(&step.radar).chain(&step.outputs) {
+            text.push_str(&format!(",{}", value));
+        }
+        text.push_str(&format!(",{}\n", step.collided));
+    }
+
+    text
+}
+
+pub fn trace_to_json(trace: &[TraceStep]) -> String {
+    let steps: Vec<String> = trace
+        .iter()
+        .map(|step| {
+            format!(
+                "{{\"step\":{},\"x\":{},\"y\":{},\"heading\":{},\"velocity\":{},\"angular_velocity\":{},\"sensors\":{},\"radar\":{},\"outputs\":{},\"collided\":{}}}",
+                step.step,
+                json_number(step.x),
+                json_number(step.y),
+                json_number(step.heading),
+                json_number(step.velocity),
+                json_number(step.angular_velocity),
+                json_array(&step.sensors),
+                json_array(&step.radar),
+                json_array(&step.outputs),
+                step.collided
+            )
+        })
+        .collect();
+
+    format!("[\n{}\n]\n", steps.join(",\n"))
+}
+
+fn json_array(values: &[f64]) -> String {
+    let values: Vec<String> = values.iter().map(|v| json_number(*v)).collect();
+    format!("[{}]", values.join(","))
+}
+
+// JSON has no representation of NaN or infinity
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{}", value)
+    } else {
+        String::from("null")
+    }
+}
+
+impl str::FromStr for TraceFormat {
+    type Err = u64;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "None" => Ok(TraceFormat::None),
+            "Csv" => Ok(TraceFormat::Csv),
+            "Json" => Ok(TraceFormat::Json),
+            _ => Err(1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace() -> Vec<TraceStep> {
+        vec![
+            TraceStep {
+                step: 0,
+                x: 0.5,
+                y: 1.5,
+                heading: 90.0,
+                velocity: 0.25,
+                angular_velocity: -1.0,
+                sensors: vec![1.0, 0.5],
+                radar: vec![0.0, 1.0],
+                outputs: vec![0.1, -0.2],
+                collided: false,
+            },
+            TraceStep {
+                step: 1,
+                x: 0.75,
+                y: f64::NAN,
+                heading: 91.0,
+                velocity: f64::INFINITY,
+                angular_velocity: 0.0,
+                sensors: vec![0.75, f64::NEG_INFINITY],
+                radar: vec![],
+                outputs: vec![0.3, 0.4],
+                collided: true,
+            },
+        ]
+    }
+
+    #[test]
+    fn csv_has_header_and_columns_in_order() {
+        let csv = trace_to_csv(&trace()[..1]);
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "step,x,y,heading,velocity,angular_velocity,sensor_0,sensor_1,radar_0,radar_1,output_0,output_1,collided"
+        );
+        assert_eq!(lines[1], "0,0.5,1.5,90,0.25,-1,1,0.5,0,1,0.1,-0.2,false");
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn empty_csv_has_fixed_columns() {
+        assert_eq!(
+            trace_to_csv(&[]),
+            "step,x,y,heading,velocity,angular_velocity,collided\n"
+        );
+    }
+
+    #[test]
+    fn json_writes_non_finite_values_as_null() {
+        let json = trace_to_json(&trace());
+
+        assert_eq!(
+            json,
+            "[\n\
+             {\"step\":0,\"x\":0.5,\"y\":1.5,\"heading\":90,\"velocity\":0.25,\"angular_velocity\":-1,\"sensors\":[1,0.5],\"radar\":[0,1],\"outputs\":[0.1,-0.2],\"collided\":false},\n\
+             {\"step\":1,\"x\":0.75,\"y\":null,\"heading\":91,\"velocity\":null,\"angular_velocity\":0,\"sensors\":[0.75,null],\"radar\":[],\"outputs\":[0.3,0.4],\"collided\":true}\n\
+             ]\n"
+        );
+    }
+}